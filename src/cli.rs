@@ -0,0 +1,212 @@
+use std::path::PathBuf;
+
+use crate::game::paths::Paths;
+
+pub const USAGE: &str = "Usage: squaretap [options] [command]
+
+Commands:
+    play                 Open the game (default)
+    highscores           Print the highscore tables
+    validate             Check the settings and difficulty curves
+    export [dir]         Export highscores and history as JSON and CSV (default \"export\")
+    import [path]        Import exported data into the save (default \"import\")
+    help                 Print this message
+
+Options:
+    --config <file>      Settings file (default \"settings.toml\")
+    --save <file>        Save file (default \"savefile.bin\")
+    --assets <dir>       Resource directory (default \"res\")
+    --windowed           Open in a window instead of fullscreen
+    --resolution <WxH>   Window size, implies --windowed
+    --mode <name>        Start a round of this mode right away
+    --seed <number>      Seed for the round started with --mode";
+
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    Play,
+    Highscores,
+    Validate,
+    Export(Option<String>),
+    Import(Option<String>),
+    Help,
+}
+
+pub struct Options {
+    pub command: Command,
+    pub paths: Paths,
+    pub windowed: bool,
+    pub resolution: Option<(i32, i32)>,
+    pub mode: Option<String>,
+    pub seed: Option<u64>,
+}
+
+impl Options {
+    pub fn parse(args: impl Iterator<Item = String>) -> Result<Options, String> {
+        let mut options = Options {
+            command: Command::Play,
+            paths: Paths::new(),
+            windowed: false,
+            resolution: None,
+            mode: None,
+            seed: None,
+        };
+        let mut command: Vec<String> = vec![];
+
+        let mut args = args;
+        while let Some(arg) = args.next() {
+            let mut value = |name: &str| {
+                return args.next().ok_or_else(|| format!("{} needs a value", name));
+            };
+
+            match arg.as_str() {
+                "--config" => options.paths.config = PathBuf::from(value(&arg)?),
+                "--save" => options.paths.save = PathBuf::from(value(&arg)?),
+                "--assets" => options.paths.assets = PathBuf::from(value(&arg)?),
+                "--windowed" => options.windowed = true,
+                "--resolution" => {
+                    options.resolution = Some(parse_resolution(&value(&arg)?)?);
+                    options.windowed = true;
+                }
+                "--mode" => options.mode = Some(value(&arg)?),
+                "--seed" => {
+                    let seed = value(&arg)?;
+                    options.seed = Some(seed.parse().map_err(|_| format!("Invalid seed \"{}\"", seed))?);
+                }
+                "-h" | "--help" => command = vec!["help".to_owned()],
+                _ if arg.starts_with("--") => return Err(format!("Unknown option \"{}\"", arg)),
+                _ => command.push(arg),
+            }
+        }
+
+        // Only export and import take an argument
+        let takes_argument = matches!(command.first().map(String::as_str), Some("export" | "import"));
+        let max_len = if takes_argument { 2 } else { 1 };
+        if let Some(extra) = command.get(max_len) {
+            return Err(format!("Unexpected argument \"{}\"", extra));
+        }
+        let argument = command.get(1).cloned();
+        options.command = match command.first().map(String::as_str) {
+            None | Some("play") => Command::Play,
+            Some("highscores") => Command::Highscores,
+            Some("validate") => Command::Validate,
+            Some("export") => Command::Export(argument),
+            Some("import") => Command::Import(argument),
+            Some("help") => Command::Help,
+            Some(other) => return Err(format!("Unknown command \"{}\"", other)),
+        };
+        if options.seed.is_some() && options.mode.is_none() {
+            return Err("--seed needs a --mode to start".to_owned());
+        }
+
+        return Ok(options);
+    }
+}
+
+// "1280x720" into (1280, 720)
+fn parse_resolution(value: &str) -> Result<(i32, i32), String> {
+    let invalid = || format!("Invalid resolution \"{}\", expected WIDTHxHEIGHT", value);
+    let (width, height) = value.split_once('x').ok_or_else(invalid)?;
+    let width: i32 = width.parse().map_err(|_| invalid())?;
+    let height: i32 = height.parse().map_err(|_| invalid())?;
+    if width <= 0 || height <= 0 {
+        return Err(invalid());
+    }
+
+    return Ok((width, height));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &str) -> Result<Options, String> {
+        return Options::parse(args.split_whitespace().map(str::to_owned));
+    }
+
+    #[test]
+    fn parses_commands() {
+        let cases = [
+            ("", Command::Play),
+            ("play", Command::Play),
+            ("highscores", Command::Highscores),
+            ("validate", Command::Validate),
+            ("export", Command::Export(None)),
+            ("export out", Command::Export(Some("out".to_owned()))),
+            ("import", Command::Import(None)),
+            ("import data.json", Command::Import(Some("data.json".to_owned()))),
+            ("help", Command::Help),
+            ("-h", Command::Help),
+            ("--help", Command::Help),
+            ("--windowed export out", Command::Export(Some("out".to_owned()))),
+        ];
+        for (args, command) in cases {
+            assert_eq!(parse(args).map(|options| options.command), Ok(command), "{:?}", args);
+        }
+    }
+
+    #[test]
+    fn parses_options() {
+        let options = parse("--config a.toml --save b.bin --assets c --mode Classic --seed 42").unwrap();
+        assert_eq!(options.paths.config, PathBuf::from("a.toml"));
+        assert_eq!(options.paths.save, PathBuf::from("b.bin"));
+        assert_eq!(options.paths.assets, PathBuf::from("c"));
+        assert_eq!(options.mode.as_deref(), Some("Classic"));
+        assert_eq!(options.seed, Some(42));
+        assert!(!options.windowed);
+
+        let defaults = parse("").unwrap();
+        assert_eq!(defaults.paths.config, Paths::new().config);
+        assert_eq!((defaults.windowed, defaults.resolution, defaults.mode, defaults.seed), (false, None, None, None));
+        assert!(parse("--windowed").unwrap().windowed);
+    }
+
+    #[test]
+    fn parses_resolutions() {
+        let cases = [
+            ("1280x720", Ok((1280, 720))),
+            ("3840x2160", Ok((3840, 2160))),
+            ("1280", Err(())),
+            ("1280x", Err(())),
+            ("x720", Err(())),
+            ("0x720", Err(())),
+            ("1280x-720", Err(())),
+            ("1280X720", Err(())),
+            ("widexhigh", Err(())),
+            ("1280x720x2", Err(())),
+        ];
+        for (value, expected) in cases {
+            let options = parse(&format!("--resolution {}", value));
+            match expected {
+                Ok(resolution) => {
+                    let options = options.unwrap();
+                    assert_eq!(options.resolution, Some(resolution), "{:?}", value);
+                    assert!(options.windowed, "{:?} should imply --windowed", value);
+                }
+                Err(()) => assert!(options.is_err(), "{:?} should be rejected", value),
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_bad_arguments() {
+        let cases = [
+            "--fullscreen",
+            "-x",
+            "--config",
+            "--resolution",
+            "--mode",
+            "--seed",
+            "--seed 42",
+            "--mode Classic --seed -1",
+            "--mode Classic --seed lots",
+            "unknown",
+            "play now",
+            "validate extra",
+            "export a b",
+            "import a b",
+        ];
+        for args in cases {
+            assert!(parse(args).is_err(), "{:?} should be rejected", args);
+        }
+    }
+}
@@ -1,55 +1,56 @@
-use std::{
-    fs::remove_file,
-    convert::TryInto,
-    path::Path
-};
-
 use macroquad::prelude::*;
 
 #[cfg(target_os = "windows")]
 use winapi::um::winuser::ShowCursor;
 
-mod highscore;
+mod achievements;
+mod audio;
+mod crosshair;
+mod difficulty;
+pub mod export;
+mod feedback;
+mod heatmap;
+pub mod highscore;
+mod history;
+mod input;
+mod json;
+pub mod layout;
+mod locale;
+mod menu;
+mod mode;
+mod mode_select;
 mod pattern;
-mod settings;
+pub mod profiles;
+pub mod paths;
+mod replay;
+mod results;
+mod screen;
+mod screens;
+pub mod settings;
+mod statistics;
+mod text;
+mod theme;
+mod tile;
 mod saves;
 
-use highscore::Highscore;
-use pattern::Pattern;
-use settings::{
-    Settings
-};
+use crosshair::CrosshairStyle;
+use layout::{at, draw_background, Anchor};
+use paths::{path_str, paths};
+use screen::{Context, Screen, Transition};
+use screens::{MainMenu, ProfilesScreen, Round};
+use settings::Settings;
+use text::TextStyle;
 use saves::{
-    GameSave,
-    load_from_file
+    load_from_file,
+    save_exists
 };
 
-#[derive(PartialEq)]
-pub enum GameState {
-    Menu,
-    Running,
-    Highscore,
-    Settings,
-    Closing,
-}
-
-#[derive(PartialEq)]
-enum MenuSelect {
-    Run,
-    Highscore,
-    Settings,
-    Close,
-}
-
 pub struct Game {
-    pub state: GameState,
-    menu_selected: MenuSelect,
+    // The main menu is always at the bottom, the screen on top is the one being played
+    screens: Vec<Box<dyn Screen>>,
+    ctx: Context,
     menu_background: Texture2D,
-    font: Font,
-
-    pattern: Pattern,
-    highscore: Highscore,
-    settings: Settings,
+    closing: bool,
     save_loaded: bool,
 }
 
@@ -61,231 +62,181 @@ impl Game {
     }
 
     pub async fn setup(&mut self) {
-        #[cfg(target_os = "windows")]
-        unsafe {
-            ShowCursor(0);
-        }
-
+        // The crosshair takes the place of the cursor on every screen
+        set_cursor_visible(false);
         self.load().await;
+        self.push(Box::new(MainMenu::new()));
+        // Whoever is playing picks their profile first when there's more than one
+        if self.ctx.profiles.list.len() > 1 {
+            self.push(Box::new(ProfilesScreen::new()));
+        }
     }
 
     pub async fn load(&mut self) {
-        if !self.save_loaded && Path::new("savefile.bin").exists() {
-            let save = load_from_file();  
-            let scores = save.highscores.as_slice().try_into().unwrap();
-            
-            self.highscore.scores = scores;
+        let ctx = &mut self.ctx;
+        if !self.save_loaded && save_exists() {
+            ctx.profiles = load_from_file().into_profiles();
+            ctx.profiles.load_active(&mut ctx.highscore, &mut ctx.history, &mut ctx.achievements);
             self.save_loaded = true;
         }
 
         let options = Settings::load_options();
-        self.pattern.tiles_filled_color = options.filled_color;
-        self.pattern.tiles_empty_color = options.empty_color;
-        self.pattern.tiles_border_color = options.border_color;
-        self.pattern.crosshair_color = options.crosshair_color;
-        self.pattern.score_color = options.score_color;
-        self.pattern.scale = options.scale;
+        locale::set_language(&options.language);
+        ctx.apply_theme(&options);
+        for warning in &options.contrast_warnings {
+            eprintln!("Warning: {}", warning);
+        }
+        ctx.crosshair.style = options.crosshair_style;
+        ctx.crosshair.size = options.crosshair_size;
+        ctx.crosshair.thickness = options.crosshair_thickness;
+        ctx.crosshair.gap = options.crosshair_gap;
+        ctx.crosshair.outline = options.crosshair_outline;
+        ctx.crosshair.outline_color = options.crosshair_outline_color;
+        ctx.pattern.scale = options.scale;
+        ctx.pattern.display_timer_bar = options.timer_bar;
+        ctx.pattern.feedback.enabled = options.effects;
+        layout::set_ui_scale(options.ui_scale);
+        ctx.audio.master_volume = options.master_volume;
+        ctx.audio.effects_volume = options.effects_volume;
+        ctx.audio.muted = options.muted;
+
+        let mode_names: Vec<&str> = options.modes.iter().map(|m| m.name.as_str()).collect();
+        ctx.highscore.add_modes(&mode_names);
+        ctx.settings.setup(&options);
+        ctx.apply_accessibility();
+        ctx.mode_select.set_modes(options.modes, options.curves);
         
         // Resources
-        self.menu_background = load_texture("res/img/menu_background.png").await.unwrap();
-        self.font = load_ttf_font("res/fonts/alagard.ttf").await.unwrap();
+        let background = path_str(&paths().asset("img/menu_background.png"));
+        self.menu_background = load_texture(&background).await.unwrap();
+        text::set_font(locale::load_font());
+        text::set_styles(&options.text_styles);
+        ctx.audio.load();
+        ctx.crosshair.image = None;
+        if options.crosshair_style == CrosshairStyle::Image {
+            match &options.crosshair_image {
+                Some(image) => match load_texture(&path_str(&paths().asset(image))).await {
+                    Ok(texture) => ctx.crosshair.image = Some(texture),
+                    Err(err) => eprintln!("Couldn't load the crosshair image, using a cross: {}", err),
+                },
+                None => eprintln!("crosshair_style is Image but there's no crosshair_image, using a cross"),
+            }
+        }
     }
 
-    pub fn save(&self) {
-        let mut temp_highscores: Vec<u32> = vec!();
-        for score in &self.highscore.scores {
-            temp_highscores.push(*score);
+    // Skips the menus and starts a round, false if there is no mode with that name
+    pub fn start_round(&mut self, mode: &str, seed: Option<u64>) -> bool {
+        if !self.ctx.mode_select.select(mode) {
+            return false;
         }
 
-        let mut save = GameSave::new_from_data(temp_highscores);
+        self.ctx.setup_round(seed);
+        self.push(Box::new(Round));
+        return true;
+    }
 
-        if Path::new("savefile.bin").exists() {
-            remove_file("savefile.bin").unwrap();
-        }
-        save.save_to_file();
+    pub fn save(&self) {
+        self.ctx.save();
     }
 
-    pub fn update(&mut self) {
-        match self.state {
-            GameState::Menu => {
-                if is_key_pressed(KeyCode::Up) {
-                    match self.menu_selected {
-                        MenuSelect::Run => {}
-                        MenuSelect::Highscore => self.menu_selected = MenuSelect::Run,
-                        MenuSelect::Settings => self.menu_selected = MenuSelect::Highscore,
-                        MenuSelect::Close => self.menu_selected = MenuSelect::Settings,
-                    }
-                } else if is_key_pressed(KeyCode::Down) {
-                    match self.menu_selected {
-                        MenuSelect::Run => self.menu_selected = MenuSelect::Highscore,
-                        MenuSelect::Highscore => self.menu_selected = MenuSelect::Settings,
-                        MenuSelect::Settings => self.menu_selected = MenuSelect::Close,
-                        MenuSelect::Close => {}
-                    }
-                }
+    pub fn closing(&self) -> bool {
+        return self.closing;
+    }
 
-                if is_key_pressed(KeyCode::Enter) {
-                    match self.menu_selected {
-                        MenuSelect::Run => {
-                            self.pattern.setup();
-                            self.state = GameState::Running;
-                        }
-                        MenuSelect::Highscore => {
-                            self.highscore.setup();
-                            self.state = GameState::Highscore;
-                        }
-                        MenuSelect::Settings => {
-                            self.state = GameState::Settings;
-                        }
-                        MenuSelect::Close => {
-                            self.state = GameState::Closing;
-                        }
-                    }
-                }
-            }
-            GameState::Running => {
-                if is_key_pressed(KeyCode::Escape) {
-                    self.state = GameState::Menu;
-                }
+    fn push(&mut self, mut screen: Box<dyn Screen>) {
+        screen.enter(&mut self.ctx);
+        self.screens.push(screen);
+    }
+
+    fn pop(&mut self) {
+        if let Some(mut screen) = self.screens.pop() {
+            screen.exit(&mut self.ctx);
+        }
+    }
 
-                self.pattern.update();
-                if self.pattern.done && !self.highscore.score_exist(self.pattern.score) {
-                    self.highscore.add_score(self.pattern.score);
+    fn apply(&mut self, transition: Transition) {
+        match transition {
+            Transition::None => {}
+            Transition::Push(screen) => self.push(screen),
+            Transition::Pop => self.pop(),
+            Transition::PopWith(result) => {
+                self.pop();
+                if let Some(screen) = self.screens.last_mut() {
+                    let transition = screen.resume(&mut self.ctx, result);
+                    self.apply(transition);
                 }
             }
-            GameState::Highscore => {
-                if is_key_pressed(KeyCode::Escape) {
-                    self.state = GameState::Menu;
-                }
-
-                self.highscore.update();
+            Transition::Replace(screen) => {
+                self.pop();
+                self.push(screen);
             }
-            GameState::Settings => {
-                if is_key_pressed(KeyCode::Escape) {
-                    self.state = GameState::Menu;
+            Transition::Home => {
+                while self.screens.len() > 1 {
+                    self.pop();
                 }
-
-                self.settings.update();
             }
-            GameState::Closing => {}
+            Transition::Quit => self.closing = true,
         }
     }
 
-    pub fn render(&mut self) {
-        let font = self.font;
-
-        clear_background(BLACK);
-        // Background
-        draw_texture(self.menu_background, 0.0, 0.0, WHITE);
-
-        match self.state {
-            GameState::Menu => {
-                let title = "SquareTap v0.1.4 - alpha";
-                let title_dimensions = measure_text(title, Some(font), 78, 1.0);
-                draw_text_ex(
-                    title,
-                    screen_width() / 2.0 - title_dimensions.width / 2.0,
-                    screen_height() / 2.0 - 250.0,
-                    TextParams {
-                        font,
-                        font_size: 78,
-                        color: WHITE,
-                        ..Default::default()
-                    },
-                );
-
-                let selected = Color::from_rgba(255, 255, 255, 255);
-                let not_selected = Color::from_rgba(255, 255, 255, 125);
-                let mut start_color = not_selected;
-                let mut highscore_color = not_selected;
-                let mut settings_color = not_selected;
-                let mut close_color = not_selected;
-                match self.menu_selected {
-                    MenuSelect::Run => start_color = selected,
-                    MenuSelect::Highscore => highscore_color = selected,
-                    MenuSelect::Settings => settings_color = selected,
-                    MenuSelect::Close => close_color = selected,
-                }
-
-                draw_text_ex(
-                    "Start",
-                    250.0,
-                    screen_height() / 2.0 - 50.0,
-                    TextParams {
-                        font,
-                        font_size: 32,
-                        color: start_color,
-                        ..Default::default()
-                    },
-                );
+    // Async to load the settings and resources of a profile when another one is picked
+    pub async fn update(&mut self) {
+        let screen = match self.screens.last_mut() {
+            Some(screen) => screen,
+            None => return,
+        };
 
-                draw_text_ex(
-                    "Highscore",
-                    250.0,
-                    screen_height() / 2.0,
-                    TextParams {
-                        font,
-                        font_size: 32,
-                        color: highscore_color,
-                        ..Default::default()
-                    },
-                );
+        let transition = screen.update(&mut self.ctx);
+        self.apply(transition);
 
-                draw_text_ex(
-                    "Settings",
-                    250.0,
-                    screen_height() / 2.0 + 50.0,
-                    TextParams {
-                        font,
-                        font_size: 32,
-                        color: settings_color,
-                        ..Default::default()
-                    },
-                );
+        if self.ctx.reload {
+            self.ctx.reload = false;
+            self.load().await;
+            self.save();
+        }
+    }
 
-                draw_text_ex(
-                    "Exit",
-                    250.0,
-                    screen_height() / 2.0 + 100.0,
-                    TextParams {
-                        font,
-                        font_size: 32,
-                        color: close_color,
-                        ..Default::default()
-                    },
-                );
-            }
-            GameState::Running => self.pattern.render(font),
-            GameState::Highscore => self.highscore.render(font),
-            GameState::Settings => self.settings.render(font),
-            GameState::Closing => {}
+    pub fn render(&mut self) {
+        clear_background(BLACK);
+        // Background
+        draw_background(self.menu_background);
+
+        // Overlays are drawn over the screens underneath them, down to the first full screen
+        let first = self
+            .screens
+            .iter()
+            .rposition(|screen| !screen.is_overlay())
+            .unwrap_or(0);
+        for screen in &mut self.screens[first..] {
+            screen.render(&mut self.ctx);
         }
 
-        draw_text_ex(
-            "v0.1.4 alpha",
-            50.0,
-            screen_height() - 32.0,
-            TextParams {
-                font,
-                font_size: 16,
-                color: WHITE,
-                ..Default::default()
-            },
-        );
+        let version = at(Anchor::BottomLeft, 50.0, -32.0);
+        draw_text_ex("v0.1.4 alpha", version.x, version.y, text::params(TextStyle::Small));
+
+        self.ctx.achievements.render_toasts();
+        self.ctx.crosshair.render(mouse_position());
     }
 }
 
 impl Default for Game {
     fn default() -> Game {
         return Game {
-            state: GameState::Menu,
-            menu_selected: MenuSelect::Run,
+            screens: vec![],
+            ctx: Context::new(),
             menu_background: Texture2D::empty(),
-            font: Font::default(),
-            pattern: Pattern::new(),
-            highscore: Highscore::new(),
-            settings: Settings::new(),
+            closing: false,
             save_loaded: false,
         };
     }
 }
+
+fn set_cursor_visible(visible: bool) {
+    #[cfg(target_os = "windows")]
+    unsafe {
+        ShowCursor(visible as i32);
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    show_mouse(visible);
+}
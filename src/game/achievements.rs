@@ -0,0 +1,234 @@
+use macroquad::prelude::*;
+
+use super::history::{History, RoundRecord};
+use super::layout::{at, draw_text_centred, px, Anchor};
+use super::locale::{tr, tr_with};
+use super::menu::{is_back_pressed, Button};
+use super::text::{self, TextStyle};
+
+// Hits in a row each faster than STREAK_REACTION seconds for QuickStreak
+const STREAK_LENGTH: usize = 10;
+const STREAK_REACTION: f32 = 0.2;
+const ROUNDS_PLAYED: usize = 50;
+// Seconds each unlock is shown, sliding in and out over the first and last SLIDE_TIME of it
+const TOAST_TIME: f64 = 3.0;
+const SLIDE_TIME: f64 = 0.25;
+// Reference pixels, the toast is wider when the name doesn't fit
+const TOAST_WIDTH: f32 = 460.0;
+const TOAST_HEIGHT: f32 = 90.0;
+const TOAST_BACKGROUND: Color = color_u8!(0, 0, 0, 200);
+// How much of the text's color locked achievements are drawn with
+const LOCKED_ALPHA: f32 = 0.35;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Achievement {
+    FirstRound,
+    Score100,
+    Score250,
+    QuickStreak,
+    PerfectRound,
+    Rounds50,
+}
+
+impl Achievement {
+    pub const ALL: [Achievement; 6] = [
+        Achievement::FirstRound,
+        Achievement::Score100,
+        Achievement::Score250,
+        Achievement::QuickStreak,
+        Achievement::PerfectRound,
+        Achievement::Rounds50,
+    ];
+
+    // Kept in the save and used for the messages, so they mustn't change
+    const fn id(self) -> &'static str {
+        match self {
+            Achievement::FirstRound => return "first_round",
+            Achievement::Score100 => return "score_100",
+            Achievement::Score250 => return "score_250",
+            Achievement::QuickStreak => return "quick_streak",
+            Achievement::PerfectRound => return "perfect_round",
+            Achievement::Rounds50 => return "rounds_50",
+        }
+    }
+
+    pub fn name(self) -> String {
+        return tr(&format!("achievements.{}", self.id()));
+    }
+
+    pub fn description(self) -> String {
+        return tr_with(
+            &format!("achievements.{}_description", self.id()),
+            &[
+                ("streak", STREAK_LENGTH.to_string()),
+                ("reaction", format!("{:.0}", STREAK_REACTION * 1000.0)),
+                ("rounds", ROUNDS_PLAYED.to_string()),
+            ],
+        );
+    }
+
+    // Rounds played with assists count for playing, not for these
+    fn needs_unassisted(self) -> bool {
+        return matches!(
+            self,
+            Achievement::Score100 | Achievement::Score250 | Achievement::QuickStreak | Achievement::PerfectRound
+        );
+    }
+
+    // Whether the round just played, already in the history, unlocks it
+    fn unlocked_by(self, round: &RoundRecord, history: &History) -> bool {
+        match self {
+            Achievement::FirstRound => return true,
+            Achievement::Score100 => return round.score >= 100,
+            Achievement::Score250 => return round.score >= 250,
+            Achievement::QuickStreak => {
+                let mut streak = 0;
+                for tap in &round.taps {
+                    if tap.hit && tap.reaction < STREAK_REACTION {
+                        streak += 1;
+                        if streak >= STREAK_LENGTH {
+                            return true;
+                        }
+                    } else {
+                        streak = 0;
+                    }
+                }
+                return false;
+            }
+            // Played to the end without a miss or an expired tile
+            Achievement::PerfectRound => {
+                return !round.taps.is_empty() && round.taps.iter().all(|tap| tap.hit) && round.expired == 0;
+            }
+            Achievement::Rounds50 => return history.rounds.len() >= ROUNDS_PLAYED,
+        }
+    }
+}
+
+// The active profile's unlocked achievements, the screen listing them and toasts for new ones
+pub struct Achievements {
+    // Ids of the unlocked achievements, in the order they were unlocked
+    pub unlocked: Vec<String>,
+    // Toasts without sliding
+    pub reduced_motion: bool,
+    toasts: Vec<Achievement>,
+    // When the first toast was first drawn
+    toast_started: Option<f64>,
+    back: Button,
+}
+
+impl Achievements {
+    pub fn new() -> Self {
+        return Self {
+            ..Default::default()
+        };
+    }
+
+    pub fn setup(&mut self) {
+        self.back.label = tr("button.back");
+    }
+
+    pub fn is_unlocked(&self, achievement: Achievement) -> bool {
+        return self.unlocked.iter().any(|id| id == achievement.id());
+    }
+
+    // Unlocks what the round earned and shows a toast for each
+    pub fn check_round(&mut self, round: &RoundRecord, history: &History) {
+        for achievement in Achievement::ALL {
+            if self.is_unlocked(achievement) || (achievement.needs_unassisted() && round.assisted()) {
+                continue;
+            }
+            if achievement.unlocked_by(round, history) {
+                self.unlocked.push(achievement.id().to_owned());
+                self.toasts.push(achievement);
+            }
+        }
+    }
+
+    // True when the screen should be left
+    pub fn update(&mut self) -> bool {
+        return is_back_pressed() || self.back.clicked();
+    }
+
+    pub fn render(&mut self) {
+        self.back.render_back();
+
+        let title = &tr("achievements.title");
+        let title_position = at(Anchor::Centre, 0.0, -250.0);
+        draw_text_centred(title, title_position.x, title_position.y, text::params(TextStyle::Title));
+
+        let count = Achievement::ALL.iter().filter(|&&achievement| self.is_unlocked(achievement)).count();
+        let progress = tr_with(
+            "achievements.progress",
+            &[("unlocked", count.to_string()), ("total", Achievement::ALL.len().to_string())],
+        );
+        let progress_position = at(Anchor::Centre, 0.0, -200.0);
+        draw_text_centred(&progress, progress_position.x, progress_position.y, text::params(TextStyle::Small));
+
+        for (i, achievement) in Achievement::ALL.iter().enumerate() {
+            let mut name = text::params(TextStyle::MenuItem);
+            let mut description = text::params(TextStyle::Small);
+            if !self.is_unlocked(*achievement) {
+                name.color.a *= LOCKED_ALPHA;
+                description.color.a *= LOCKED_ALPHA;
+            }
+
+            let position = at(Anchor::Left, 250.0, -140.0 + i as f32 * 70.0);
+            draw_text_ex(&achievement.name(), position.x, position.y, name);
+            draw_text_ex(&achievement.description(), position.x, position.y + px(26.0), description);
+        }
+    }
+
+    // Shows the toasts one after the other in the top right corner, over every screen
+    pub fn render_toasts(&mut self) {
+        let achievement = match self.toasts.first() {
+            Some(&achievement) => achievement,
+            None => return,
+        };
+
+        let now = get_time();
+        let started = *self.toast_started.get_or_insert(now);
+        let shown = now - started;
+        if shown >= TOAST_TIME {
+            self.toasts.remove(0);
+            self.toast_started = None;
+            return;
+        }
+
+        // Slides in from the right and back out
+        let slide = if self.reduced_motion {
+            0.0
+        } else {
+            let t = (shown.min(TOAST_TIME - shown) / SLIDE_TIME).min(1.0) as f32;
+            1.0 - t * (2.0 - t)
+        };
+        let name = achievement.name();
+        let width = px(TOAST_WIDTH).max(text::measure(&name, TextStyle::MenuItem).width + px(40.0));
+        let mut corner = at(Anchor::TopRight, -50.0, 40.0);
+        corner.x += (slide * (px(50.0) + width)) - width;
+        draw_rectangle(corner.x, corner.y, width, px(TOAST_HEIGHT), TOAST_BACKGROUND);
+        draw_text_ex(
+            &tr("achievements.unlocked"),
+            corner.x + px(20.0),
+            corner.y + px(32.0),
+            text::params(TextStyle::Small),
+        );
+        draw_text_ex(
+            &name,
+            corner.x + px(20.0),
+            corner.y + px(70.0),
+            text::params(TextStyle::MenuItem),
+        );
+    }
+}
+
+impl Default for Achievements {
+    fn default() -> Achievements {
+        return Achievements {
+            unlocked: vec![],
+            reduced_motion: false,
+            toasts: vec![],
+            toast_started: None,
+            back: Button::new(&tr("button.back")),
+        };
+    }
+}
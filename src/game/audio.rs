@@ -0,0 +1,133 @@
+#[cfg(feature = "audio")]
+use std::{collections::HashMap, fs, io::Cursor, sync::Arc};
+
+#[cfg(feature = "audio")]
+use rodio::{Decoder, OutputStream, OutputStreamHandle, Source};
+
+#[cfg(feature = "audio")]
+use super::paths::paths;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Effect {
+    Hit,
+    Miss,
+    // Every COMBO_TIER hits in a row
+    ComboTier,
+    // Each of the last seconds of a round
+    Countdown,
+    RoundEnd,
+    // The metronome
+    Beat,
+}
+
+#[cfg(feature = "audio")]
+impl Effect {
+    const ALL: [Effect; 6] = [
+        Effect::Hit,
+        Effect::Miss,
+        Effect::ComboTier,
+        Effect::Countdown,
+        Effect::RoundEnd,
+        Effect::Beat,
+    ];
+
+    fn file(self) -> &'static str {
+        match self {
+            Effect::Hit => return "sounds/hit.wav",
+            Effect::Miss => return "sounds/miss.wav",
+            Effect::ComboTier => return "sounds/combo.wav",
+            Effect::Countdown => return "sounds/countdown.wav",
+            Effect::RoundEnd => return "sounds/round_end.wav",
+            Effect::Beat => return "sounds/beat.wav",
+        }
+    }
+}
+
+// Built without the audio feature there's no sound at all, the volume settings are still kept
+pub struct Audio {
+    // None without an audio device, everything is silent then
+    #[cfg(feature = "audio")]
+    output: Option<(OutputStream, OutputStreamHandle)>,
+    #[cfg(feature = "audio")]
+    sounds: HashMap<Effect, Arc<[u8]>>,
+    pub master_volume: f32,
+    pub effects_volume: f32,
+    pub muted: bool,
+}
+
+impl Audio {
+    pub fn new() -> Self {
+        return Self {
+            ..Default::default()
+        };
+    }
+
+    // Opens the default audio device and reads the sounds. Missing sounds or a missing
+    // device are reported and the game carries on without them.
+    #[cfg(feature = "audio")]
+    pub fn load(&mut self) {
+        if self.output.is_none() {
+            match OutputStream::try_default() {
+                Ok(output) => self.output = Some(output),
+                Err(err) => eprintln!("No audio, playing without sound: {}", err),
+            }
+        }
+
+        for &effect in Effect::ALL.iter() {
+            let path = paths().asset(effect.file());
+            match fs::read(&path) {
+                Ok(data) => {
+                    self.sounds.insert(effect, data.into());
+                }
+                Err(err) => eprintln!("Couldn't load {}: {}", path.display(), err),
+            }
+        }
+    }
+
+    #[cfg(feature = "audio")]
+    fn volume(&self) -> f32 {
+        if self.muted {
+            return 0.0;
+        }
+
+        return self.master_volume * self.effects_volume;
+    }
+
+    #[cfg(not(feature = "audio"))]
+    pub fn load(&mut self) {}
+
+    #[cfg(feature = "audio")]
+    pub fn play(&self, effect: Effect) {
+        let volume = self.volume();
+        let handle = match &self.output {
+            Some((_, handle)) if volume > 0.0 => handle,
+            _ => return,
+        };
+        let data = match self.sounds.get(&effect) {
+            Some(data) => data.clone(),
+            None => return,
+        };
+
+        // Decoded again every time, the sounds are short and several can play at once this way
+        if let Ok(source) = Decoder::new(Cursor::new(data)) {
+            let _ = handle.play_raw(source.convert_samples().amplify(volume));
+        }
+    }
+
+    #[cfg(not(feature = "audio"))]
+    pub fn play(&self, _effect: Effect) {}
+}
+
+impl Default for Audio {
+    fn default() -> Audio {
+        return Audio {
+            #[cfg(feature = "audio")]
+            output: None,
+            #[cfg(feature = "audio")]
+            sounds: HashMap::new(),
+            master_volume: 1.0,
+            effects_volume: 1.0,
+            muted: false,
+        };
+    }
+}
@@ -0,0 +1,109 @@
+use macroquad::prelude::*;
+
+use serde_derive::Deserialize;
+
+use super::layout::px;
+
+#[derive(Deserialize, Clone, Copy, PartialEq)]
+pub enum CrosshairStyle {
+    Cross,
+    Dot,
+    Circle,
+    // A cross with a gap in the middle
+    GapCross,
+    // An image from the resources tinted with the color, falls back to Cross if it can't be loaded
+    Image,
+}
+
+// Drawn at the mouse on every screen in place of the system cursor
+pub struct Crosshair {
+    pub style: CrosshairStyle,
+    pub color: Color,
+    // In reference pixels. Size is the length of the arms, the radius of the circle and the
+    // diameter of the dot, gap is the space between the arms and the centre of a gap cross.
+    pub size: f32,
+    pub thickness: f32,
+    pub gap: f32,
+    // Thickness of the outline around the shape, 0 for none
+    pub outline: f32,
+    pub outline_color: Color,
+    pub image: Option<Texture2D>,
+}
+
+impl Crosshair {
+    pub fn new() -> Self {
+        return Self {
+            ..Default::default()
+        };
+    }
+
+    pub fn render(&self, position: (f32, f32)) {
+        let centre = vec2(position.0, position.1);
+
+        if self.style == CrosshairStyle::Image {
+            if let Some(image) = self.image {
+                let width = px(self.size * 2.0);
+                let size = vec2(width, width * image.height() / image.width());
+                draw_texture_ex(
+                    image,
+                    centre.x - size.x / 2.0,
+                    centre.y - size.y / 2.0,
+                    self.color,
+                    DrawTextureParams {
+                        dest_size: Some(size),
+                        ..Default::default()
+                    },
+                );
+                return;
+            }
+        }
+
+        if self.outline > 0.0 {
+            self.draw_shape(centre, px(self.outline), self.outline_color);
+        }
+        self.draw_shape(centre, 0.0, self.color);
+    }
+
+    // The shape grown by `extra` screen pixels on every side, for the outline
+    fn draw_shape(&self, centre: Vec2, extra: f32, color: Color) {
+        let (size, thickness, gap) = (px(self.size), px(self.thickness), px(self.gap));
+
+        match self.style {
+            CrosshairStyle::Cross | CrosshairStyle::Image => {
+                draw_arm(centre, vec2(0.0, 1.0), -size - extra, size + extra, thickness + extra * 2.0, color);
+                draw_arm(centre, vec2(1.0, 0.0), -size - extra, size + extra, thickness + extra * 2.0, color);
+            }
+            CrosshairStyle::GapCross => {
+                for direction in [vec2(0.0, 1.0), vec2(0.0, -1.0), vec2(1.0, 0.0), vec2(-1.0, 0.0)] {
+                    draw_arm(centre, direction, gap - extra, gap + size + extra, thickness + extra * 2.0, color);
+                }
+            }
+            CrosshairStyle::Dot => draw_circle(centre.x, centre.y, size / 2.0 + extra, color),
+            CrosshairStyle::Circle => {
+                draw_circle_lines(centre.x, centre.y, size, thickness + extra * 2.0, color);
+            }
+        }
+    }
+}
+
+impl Default for Crosshair {
+    fn default() -> Crosshair {
+        return Crosshair {
+            style: CrosshairStyle::Cross,
+            color: RED,
+            size: 5.0,
+            thickness: 2.0,
+            gap: 3.0,
+            outline: 0.0,
+            outline_color: BLACK,
+            image: None,
+        };
+    }
+}
+
+// A line along `direction` from `from` to `to` pixels away from the centre
+fn draw_arm(centre: Vec2, direction: Vec2, from: f32, to: f32, thickness: f32, color: Color) {
+    let start = centre + direction * from;
+    let end = centre + direction * to;
+    draw_line(start.x, start.y, end.x, end.y, thickness, color);
+}
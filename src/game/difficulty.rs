@@ -0,0 +1,180 @@
+use std::fs;
+
+use serde_derive::Deserialize;
+
+use super::paths::paths;
+
+const CURVES_FILE: &str = "difficulty.toml";
+
+#[derive(Deserialize)]
+struct CurveFile {
+    #[serde(default)]
+    curves: Vec<DifficultyCurve>,
+}
+
+// Board settings once the score reaches `score`, values in between steps are interpolated
+#[derive(Deserialize, Clone, Copy)]
+pub struct DifficultyStep {
+    pub score: u32,
+    #[serde(default = "default_lit_tiles")]
+    pub lit_tiles: f32,
+    // Multiplies the player's own scale
+    #[serde(default = "default_scale")]
+    pub scale: f32,
+    // Board drifts around the screen centre within this radius in pixels
+    #[serde(default)]
+    pub move_radius: f32,
+    // Drift speed in radians per second
+    #[serde(default)]
+    pub move_speed: f32,
+    // Board rotation in degrees per second
+    #[serde(default)]
+    pub rotation_speed: f32,
+    // Multiplies the mode's tile lifetime
+    #[serde(default = "default_scale")]
+    pub lifetime_scale: f32,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct DifficultyCurve {
+    pub name: String,
+    pub steps: Vec<DifficultyStep>,
+}
+
+fn default_lit_tiles() -> f32 {
+    return 3.0;
+}
+
+fn default_scale() -> f32 {
+    return 1.0;
+}
+
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    return a + (b - a) * t;
+}
+
+impl DifficultyStep {
+    fn lerp(&self, other: &DifficultyStep, t: f32) -> DifficultyStep {
+        return DifficultyStep {
+            score: self.score,
+            lit_tiles: lerp(self.lit_tiles, other.lit_tiles, t),
+            scale: lerp(self.scale, other.scale, t),
+            move_radius: lerp(self.move_radius, other.move_radius, t),
+            move_speed: lerp(self.move_speed, other.move_speed, t),
+            rotation_speed: lerp(self.rotation_speed, other.rotation_speed, t),
+            lifetime_scale: lerp(self.lifetime_scale, other.lifetime_scale, t),
+        };
+    }
+}
+
+impl Default for DifficultyStep {
+    fn default() -> DifficultyStep {
+        return DifficultyStep {
+            score: 0,
+            lit_tiles: default_lit_tiles(),
+            scale: default_scale(),
+            move_radius: 0.0,
+            move_speed: 0.0,
+            rotation_speed: 0.0,
+            lifetime_scale: default_scale(),
+        };
+    }
+}
+
+impl DifficultyCurve {
+    pub fn at(&self, score: u32) -> DifficultyStep {
+        let first = match self.steps.first() {
+            Some(step) => step,
+            None => return DifficultyStep::default(),
+        };
+        if score <= first.score {
+            return *first;
+        }
+
+        for pair in self.steps.windows(2) {
+            let (from, to) = (&pair[0], &pair[1]);
+            if score < to.score {
+                let t = (score - from.score) as f32 / (to.score - from.score) as f32;
+                return from.lerp(to, t);
+            }
+        }
+
+        return *self.steps.last().unwrap();
+    }
+}
+
+// Curves from the assets directory, none if the file doesn't exist
+pub fn load_curves() -> Result<Vec<DifficultyCurve>, String> {
+    let path = paths().asset(CURVES_FILE);
+    if !path.exists() {
+        return Ok(vec![]);
+    }
+
+    let invalid = |err: &dyn std::fmt::Display| format!("{}: {}", path.display(), err);
+    let curves_str = fs::read_to_string(&path).map_err(|err| invalid(&err))?;
+    return parse_curves(&curves_str).map_err(|err| invalid(&err));
+}
+
+// Steps can be written in any order, they're sorted by score
+fn parse_curves(text: &str) -> Result<Vec<DifficultyCurve>, toml::de::Error> {
+    let mut file: CurveFile = toml::from_str(text)?;
+    for curve in &mut file.curves {
+        curve.steps.sort_by_key(|step| step.score);
+    }
+
+    return Ok(file.curves);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn curve(steps: &[(u32, f32)]) -> DifficultyCurve {
+        return DifficultyCurve {
+            name: "Test".to_owned(),
+            steps: steps
+                .iter()
+                .map(|&(score, lit_tiles)| DifficultyStep {
+                    score,
+                    lit_tiles,
+                    ..Default::default()
+                })
+                .collect(),
+        };
+    }
+
+    #[test]
+    fn interpolates_between_steps() {
+        let curve = curve(&[(10, 2.0), (20, 4.0), (40, 8.0)]);
+        let cases = [(0, 2.0), (10, 2.0), (15, 3.0), (20, 4.0), (25, 5.0), (39, 7.8), (40, 8.0), (1000, 8.0)];
+        for (score, lit_tiles) in cases {
+            let step = curve.at(score);
+            assert!((step.lit_tiles - lit_tiles).abs() < 1e-5, "{} at {}", step.lit_tiles, score);
+        }
+    }
+
+    #[test]
+    fn curves_without_steps_use_the_defaults() {
+        assert_eq!(curve(&[]).at(50).lit_tiles, default_lit_tiles());
+        assert_eq!(curve(&[(5, 6.0)]).at(50).lit_tiles, 6.0);
+    }
+
+    #[test]
+    fn sorts_unsorted_steps() {
+        let text = "[[curves]]\nname = \"Test\"\n\
+                    [[curves.steps]]\nscore = 40\nlit_tiles = 8.0\n\
+                    [[curves.steps]]\nscore = 10\nlit_tiles = 2.0\n\
+                    [[curves.steps]]\nscore = 20\nlit_tiles = 4.0\n";
+        let curves = parse_curves(text).unwrap();
+        let scores: Vec<u32> = curves[0].steps.iter().map(|step| step.score).collect();
+        assert_eq!(scores, vec![10, 20, 40]);
+        assert_eq!(curves[0].at(30).lit_tiles, 6.0);
+    }
+
+    #[test]
+    fn rejects_malformed_curves() {
+        for text in ["[[curves]]\nsteps = []", "[[curves]]\nname = \"Test\"\nsteps = [{ lit_tiles = 2.0 }]", "curves = 1"] {
+            assert!(parse_curves(text).is_err(), "{:?} should be rejected", text);
+        }
+    }
+}
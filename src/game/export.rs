@@ -0,0 +1,607 @@
+use std::{
+    ffi::OsStr,
+    fs::{self, create_dir_all},
+    path::Path,
+    process::exit,
+};
+
+use super::highscore::{Highscore, HighscoreTable};
+use super::history::{History, RoundRecord};
+use super::json::Json;
+use super::pattern::{Tap, CELLS};
+use super::saves::{load_data, save_data};
+
+pub const EXPORT_DIR: &str = "export";
+pub const IMPORT_DIR: &str = "import";
+
+const JSON_FILE: &str = "squaretap.json";
+const HIGHSCORES_FILE: &str = "highscores.csv";
+const ROUNDS_FILE: &str = "rounds.csv";
+const TAPS_FILE: &str = "taps.csv";
+
+// Highscores and rounds read from an exported file, before they're merged in
+struct ImportData {
+    tables: Vec<HighscoreTable>,
+    rounds: Vec<RoundRecord>,
+}
+
+pub struct ImportSummary {
+    pub scores: usize,
+    pub rounds: usize,
+    pub duplicates: usize,
+}
+
+// Writes highscores and history as both JSON and CSV files into `dir`
+pub fn export(dir: &Path, highscore: &Highscore, history: &History) -> Result<(), String> {
+    create_dir_all(dir).map_err(|err| err.to_string())?;
+
+    let write = |name: &str, contents: String| {
+        return fs::write(dir.join(name), contents).map_err(|err| format!("{}: {}", name, err));
+    };
+    write(JSON_FILE, to_json(highscore, history).to_string())?;
+    write(HIGHSCORES_FILE, highscores_csv(highscore))?;
+    write(ROUNDS_FILE, rounds_csv(history))?;
+    write(TAPS_FILE, taps_csv(history))?;
+
+    return Ok(());
+}
+
+// Merges exported data into the highscores and history. `path` is either a JSON file,
+// one of the CSV files, or a directory whose JSON files are imported, or its CSV files when it has none.
+pub fn import(
+    path: &Path,
+    highscore: &mut Highscore,
+    history: &mut History,
+) -> Result<ImportSummary, String> {
+    let mut files = vec![];
+    if path.is_dir() {
+        let entries = fs::read_dir(path).map_err(|err| err.to_string())?;
+        for entry in entries.flatten() {
+            if has_extension(&entry.path(), "json") {
+                files.push(read_json(&entry.path())?);
+            }
+        }
+        // An export has the same data as JSON and CSV, the CSV is only read when there's no JSON
+        let csv = path.join(HIGHSCORES_FILE).exists() || path.join(ROUNDS_FILE).exists();
+        if files.is_empty() && csv {
+            files.push(read_csv(path)?);
+        }
+    } else if has_extension(path, "json") {
+        files.push(read_json(path)?);
+    } else if has_extension(path, "csv") {
+        files.push(read_csv(path.parent().unwrap_or_else(|| Path::new(".")))?);
+    } else {
+        return Err(format!("Nothing to import at {}", path.display()));
+    }
+
+    let mut summary = ImportSummary {
+        scores: 0,
+        rounds: 0,
+        duplicates: 0,
+    };
+    for data in files {
+        for table in data.tables {
+            for score in table.scores.into_iter().filter(|&score| score > 0) {
+                if !highscore.score_exist(&table.mode, score) {
+                    highscore.add_score(&table.mode, score);
+                    if highscore.score_exist(&table.mode, score) {
+                        summary.scores += 1;
+                    }
+                }
+            }
+        }
+
+        for round in data.rounds {
+            if history.contains(&round) {
+                summary.duplicates += 1;
+            } else {
+                history.add(round);
+                summary.rounds += 1;
+            }
+        }
+    }
+    history.rounds.sort_by_key(|round| round.timestamp);
+
+    return Ok(summary);
+}
+
+// `squaretap export [dir]`, exports the saved data without opening the game
+pub fn export_command(dir: Option<&str>) {
+    let dir = dir.unwrap_or(EXPORT_DIR);
+    let (highscore, history) = load_data();
+
+    match export(Path::new(dir), &highscore, &history) {
+        Ok(()) => println!("Exported {} rounds to {}", history.rounds.len(), dir),
+        Err(err) => {
+            eprintln!("Export failed: {}", err);
+            exit(1);
+        }
+    }
+}
+
+// `squaretap import <path>`, merges exported data into the save without opening the game
+pub fn import_command(path: Option<&str>) {
+    let path = path.unwrap_or(IMPORT_DIR);
+    let (mut highscore, mut history) = load_data();
+
+    match import(Path::new(path), &mut highscore, &mut history) {
+        Ok(summary) => {
+            save_data(&highscore, &history);
+            println!(
+                "Imported {} highscores and {} rounds, skipped {} duplicate rounds",
+                summary.scores, summary.rounds, summary.duplicates
+            );
+        }
+        Err(err) => {
+            eprintln!("Import failed: {}", err);
+            exit(1);
+        }
+    }
+}
+
+fn has_extension(path: &Path, extension: &str) -> bool {
+    return path.extension() == Some(OsStr::new(extension));
+}
+
+fn to_json(highscore: &Highscore, history: &History) -> Json {
+    let tables = highscore
+        .tables
+        .iter()
+        .map(|table| {
+            return Json::Object(vec![
+                ("mode".to_owned(), Json::string(&table.mode)),
+                (
+                    "scores".to_owned(),
+                    Json::Array(table.scores.iter().map(Json::number).collect()),
+                ),
+            ]);
+        })
+        .collect();
+
+    let rounds = history
+        .rounds
+        .iter()
+        .map(|round| {
+            let taps = round
+                .taps
+                .iter()
+                .map(|tap| {
+                    return Json::Object(vec![
+                        ("time".to_owned(), Json::number(tap.time)),
+                        ("reaction".to_owned(), Json::number(tap.reaction)),
+                        ("cell".to_owned(), Json::number(tap.cell)),
+                        ("offset_x".to_owned(), Json::number(tap.offset_x)),
+                        ("offset_y".to_owned(), Json::number(tap.offset_y)),
+                        ("hit".to_owned(), Json::Bool(tap.hit)),
+                    ]);
+                })
+                .collect();
+
+            return Json::Object(vec![
+                ("timestamp".to_owned(), Json::number(round.timestamp)),
+                ("mode".to_owned(), Json::string(&round.mode)),
+                ("seed".to_owned(), Json::number(round.seed)),
+                ("score".to_owned(), Json::number(round.score)),
+                ("length".to_owned(), Json::number(round.length)),
+                ("scale".to_owned(), Json::number(round.scale)),
+                ("mode_duration".to_owned(), Json::number(round.mode_duration)),
+                ("tile_lifetime".to_owned(), Json::number(round.tile_lifetime)),
+                ("expired".to_owned(), Json::number(round.expired)),
+                ("timer_scale".to_owned(), Json::number(round.timer_scale)),
+                ("miss_tolerance".to_owned(), Json::number(round.miss_tolerance)),
+                ("taps".to_owned(), Json::Array(taps)),
+            ]);
+        })
+        .collect();
+
+    return Json::Object(vec![
+        ("highscores".to_owned(), Json::Array(tables)),
+        ("rounds".to_owned(), Json::Array(rounds)),
+    ]);
+}
+
+fn read_json(path: &Path) -> Result<ImportData, String> {
+    let text = fs::read_to_string(path).map_err(|err| format!("{}: {}", path.display(), err))?;
+    let json = Json::parse(&text).map_err(|err| format!("{}: {}", path.display(), err))?;
+    let invalid = |what: &str| format!("{}: invalid {}", path.display(), what);
+
+    let mut data = ImportData {
+        tables: vec![],
+        rounds: vec![],
+    };
+    for table in json.get("highscores").and_then(Json::as_array).into_iter().flatten() {
+        let mode = table.get("mode").and_then(Json::as_str).ok_or_else(|| invalid("highscore"))?;
+        let scores = table
+            .get("scores")
+            .and_then(Json::as_array)
+            .ok_or_else(|| invalid("highscore"))?
+            .iter()
+            .map(|score| score.parse_number().ok_or_else(|| invalid("highscore")))
+            .collect::<Result<Vec<u32>, String>>()?;
+
+        data.tables.push(HighscoreTable {
+            mode: mode.to_owned(),
+            scores,
+        });
+    }
+
+    for round in json.get("rounds").and_then(Json::as_array).into_iter().flatten() {
+        let field = |name: &str| round.get(name).ok_or_else(|| invalid("round"));
+        let number = |name: &str| field(name).map(|value| value.parse_number::<f64>());
+        // Exports from before assists were kept have no assists
+        let assist = |name: &str, default: f64| round.get(name).map_or(Some(default), Json::parse_number::<f64>);
+
+        let mut taps = vec![];
+        for tap in field("taps")?.as_array().ok_or_else(|| invalid("round"))? {
+            let tap_number = |name: &str| {
+                return tap
+                    .get(name)
+                    .and_then(|value| value.parse_number::<f32>())
+                    .ok_or_else(|| invalid("tap"));
+            };
+            taps.push(Tap {
+                time: tap_number("time")?,
+                reaction: tap_number("reaction")?,
+                // Whole numbers on the board only, the heatmap indexes its cells with it
+                cell: tap
+                    .get("cell")
+                    .and_then(|value| value.parse_number::<u8>())
+                    .filter(|&cell| (cell as usize) < CELLS)
+                    .ok_or_else(|| invalid("tap"))?,
+                offset_x: tap_number("offset_x")?,
+                offset_y: tap_number("offset_y")?,
+                hit: tap.get("hit").and_then(Json::as_bool).ok_or_else(|| invalid("tap"))?,
+            });
+        }
+
+        data.rounds.push(RoundRecord {
+            timestamp: field("timestamp")?.parse_number().ok_or_else(|| invalid("round"))?,
+            mode: field("mode")?.as_str().ok_or_else(|| invalid("round"))?.to_owned(),
+            seed: field("seed")?.parse_number().ok_or_else(|| invalid("round"))?,
+            score: field("score")?.parse_number().ok_or_else(|| invalid("round"))?,
+            length: number("length")?.ok_or_else(|| invalid("round"))? as f32,
+            scale: number("scale")?.ok_or_else(|| invalid("round"))? as f32,
+            mode_duration: number("mode_duration")?.ok_or_else(|| invalid("round"))? as f32,
+            tile_lifetime: number("tile_lifetime")?.ok_or_else(|| invalid("round"))? as f32,
+            expired: field("expired")?.parse_number().ok_or_else(|| invalid("round"))?,
+            taps,
+            timer_scale: assist("timer_scale", 1.0).ok_or_else(|| invalid("round"))? as f32,
+            miss_tolerance: assist("miss_tolerance", 0.0)
+                .filter(|misses| misses.fract() == 0.0 && *misses >= 0.0)
+                .ok_or_else(|| invalid("round"))? as u32,
+        });
+    }
+
+    return Ok(data);
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains(',') || value.contains('"') || value.contains('\n') {
+        return format!("\"{}\"", value.replace('"', "\"\""));
+    }
+
+    return value.to_owned();
+}
+
+fn csv_split(line: &str) -> Vec<String> {
+    let mut fields = vec![];
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(std::mem::take(&mut field)),
+            c => field.push(c),
+        }
+    }
+    fields.push(field);
+
+    return fields;
+}
+
+fn highscores_csv(highscore: &Highscore) -> String {
+    let mut csv = String::from("mode,rank,score\n");
+    for table in &highscore.tables {
+        for (rank, score) in table.scores.iter().enumerate() {
+            csv += &format!("{},{},{}\n", csv_field(&table.mode), rank + 1, score);
+        }
+    }
+
+    return csv;
+}
+
+fn rounds_csv(history: &History) -> String {
+    let mut csv = String::from(
+        "timestamp,mode,seed,score,length,scale,mode_duration,tile_lifetime,expired,timer_scale,miss_tolerance\n",
+    );
+    for round in &history.rounds {
+        csv += &format!(
+            "{},{},{},{},{},{},{},{},{},{},{}\n",
+            round.timestamp,
+            csv_field(&round.mode),
+            round.seed,
+            round.score,
+            round.length,
+            round.scale,
+            round.mode_duration,
+            round.tile_lifetime,
+            round.expired,
+            round.timer_scale,
+            round.miss_tolerance
+        );
+    }
+
+    return csv;
+}
+
+// Taps belong to the round with the same timestamp and seed
+fn taps_csv(history: &History) -> String {
+    let mut csv = String::from("timestamp,seed,time,reaction,cell,offset_x,offset_y,hit\n");
+    for round in &history.rounds {
+        for tap in &round.taps {
+            csv += &format!(
+                "{},{},{},{},{},{},{},{}\n",
+                round.timestamp,
+                round.seed,
+                tap.time,
+                tap.reaction,
+                tap.cell,
+                tap.offset_x,
+                tap.offset_y,
+                tap.hit
+            );
+        }
+    }
+
+    return csv;
+}
+
+// Reads the rows of a CSV file, skipping its header. Missing files have no rows.
+// Files from older versions can have fewer columns, every row has one of the counts in `columns`.
+fn read_csv_rows(path: &Path, columns: &[usize]) -> Result<Vec<Vec<String>>, String> {
+    if !path.exists() {
+        return Ok(vec![]);
+    }
+
+    let text = fs::read_to_string(path).map_err(|err| format!("{}: {}", path.display(), err))?;
+    let mut rows = vec![];
+    for (i, line) in text.lines().enumerate().skip(1) {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let row = csv_split(line);
+        if !columns.contains(&row.len()) {
+            let expected = columns.last().copied().unwrap_or(0);
+            return Err(format!("{}: line {} should have {} columns", path.display(), i + 1, expected));
+        }
+        rows.push(row);
+    }
+
+    return Ok(rows);
+}
+
+fn read_csv(dir: &Path) -> Result<ImportData, String> {
+    let invalid = |file: &str| format!("{}: invalid value", dir.join(file).display());
+    let mut data = ImportData {
+        tables: vec![],
+        rounds: vec![],
+    };
+
+    for row in read_csv_rows(&dir.join(HIGHSCORES_FILE), &[3])? {
+        let score: u32 = row[2].parse().map_err(|_| invalid(HIGHSCORES_FILE))?;
+        match data.tables.iter_mut().find(|table| table.mode == row[0]) {
+            Some(table) => table.scores.push(score),
+            None => data.tables.push(HighscoreTable {
+                mode: row[0].clone(),
+                scores: vec![score],
+            }),
+        }
+    }
+
+    // The assists were added as the last two columns
+    for row in read_csv_rows(&dir.join(ROUNDS_FILE), &[9, 11])? {
+        let parse = |i: usize| row[i].parse::<f64>().map_err(|_| invalid(ROUNDS_FILE));
+        let assisted = row.len() == 11;
+        data.rounds.push(RoundRecord {
+            timestamp: row[0].parse().map_err(|_| invalid(ROUNDS_FILE))?,
+            mode: row[1].clone(),
+            seed: row[2].parse().map_err(|_| invalid(ROUNDS_FILE))?,
+            score: row[3].parse().map_err(|_| invalid(ROUNDS_FILE))?,
+            length: parse(4)? as f32,
+            scale: parse(5)? as f32,
+            mode_duration: parse(6)? as f32,
+            tile_lifetime: parse(7)? as f32,
+            expired: row[8].parse().map_err(|_| invalid(ROUNDS_FILE))?,
+            taps: vec![],
+            timer_scale: if assisted { parse(9)? as f32 } else { 1.0 },
+            miss_tolerance: if assisted {
+                row[10].parse().map_err(|_| invalid(ROUNDS_FILE))?
+            } else {
+                0
+            },
+        });
+    }
+
+    for row in read_csv_rows(&dir.join(TAPS_FILE), &[8])? {
+        let timestamp: u64 = row[0].parse().map_err(|_| invalid(TAPS_FILE))?;
+        let seed: u64 = row[1].parse().map_err(|_| invalid(TAPS_FILE))?;
+        let parse = |i: usize| row[i].parse::<f32>().map_err(|_| invalid(TAPS_FILE));
+        let tap = Tap {
+            time: parse(2)?,
+            reaction: parse(3)?,
+            cell: row[4]
+                .parse::<u8>()
+                .ok()
+                .filter(|&cell| (cell as usize) < CELLS)
+                .ok_or_else(|| invalid(TAPS_FILE))?,
+            offset_x: parse(5)?,
+            offset_y: parse(6)?,
+            hit: row[7].parse().map_err(|_| invalid(TAPS_FILE))?,
+        };
+
+        let round = data
+            .rounds
+            .iter_mut()
+            .find(|round| round.timestamp == timestamp && round.seed == seed);
+        if let Some(round) = round {
+            round.taps.push(tap);
+        }
+    }
+
+    return Ok(data);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    // A fresh directory for each test, they run in parallel
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("squaretap-test-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        create_dir_all(&dir).unwrap();
+        return dir;
+    }
+
+    fn tap(cell: u8, hit: bool) -> Tap {
+        return Tap {
+            time: 1.0 / 3.0,
+            reaction: 0.123_456_7,
+            cell,
+            offset_x: -0.25,
+            offset_y: 0.499,
+            hit,
+        };
+    }
+
+    fn history() -> History {
+        let mut history = History::new();
+        history.add(RoundRecord {
+            timestamp: 1_700_000_000,
+            mode: "Classic".to_owned(),
+            seed: u64::MAX,
+            score: 120,
+            length: 30.0,
+            scale: 1.25,
+            mode_duration: 30.0,
+            tile_lifetime: 0.0,
+            expired: 0,
+            taps: vec![tap(0, true), tap(15, false), tap(7, true)],
+            timer_scale: 1.0,
+            miss_tolerance: 0,
+        });
+        history.add(RoundRecord {
+            timestamp: 1_700_000_100,
+            mode: "Quoted \"mode\", with a comma".to_owned(),
+            seed: 42,
+            score: 0,
+            length: 12.345_678,
+            scale: 0.5,
+            mode_duration: 10.0,
+            tile_lifetime: 1.5,
+            expired: 3,
+            taps: vec![],
+            timer_scale: 1.5,
+            miss_tolerance: 2,
+        });
+        return history;
+    }
+
+    #[test]
+    fn csv_round_trips_rounds() {
+        let dir = test_dir("csv");
+        let history = history();
+        fs::write(dir.join(ROUNDS_FILE), rounds_csv(&history)).unwrap();
+        fs::write(dir.join(TAPS_FILE), taps_csv(&history)).unwrap();
+
+        let data = read_csv(&dir).unwrap();
+        assert_eq!(data.rounds, history.rounds);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn json_round_trips_rounds() {
+        let dir = test_dir("json");
+        let history = history();
+        let path = dir.join(JSON_FILE);
+        fs::write(&path, to_json(&Highscore::new(), &history).to_string()).unwrap();
+
+        let data = read_json(&path).unwrap();
+        assert_eq!(data.rounds, history.rounds);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn imports_an_export_once() {
+        let dir = test_dir("export");
+        let history = history();
+        export(&dir, &Highscore::new(), &history).unwrap();
+
+        let mut imported = History::new();
+        let summary = import(&dir, &mut Highscore::new(), &mut imported).unwrap();
+        assert_eq!((summary.rounds, summary.duplicates), (2, 0));
+        assert_eq!(imported.rounds, history.rounds);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn csv_splits_quoted_fields() {
+        assert_eq!(csv_split("a,\"b,c\",\"d \"\"e\"\"\",,f"), vec!["a", "b,c", "d \"e\"", "", "f"]);
+        let field = "x, \"y\"";
+        assert_eq!(csv_split(&csv_field(field)), vec![field]);
+    }
+
+    #[test]
+    fn rejects_cells_off_the_board() {
+        let dir = test_dir("csv-cells");
+        fs::write(dir.join(ROUNDS_FILE), rounds_csv(&history())).unwrap();
+        for cell in ["16", "255", "300", "-1", "1.5"] {
+            let taps = format!(
+                "timestamp,seed,time,reaction,cell,offset_x,offset_y,hit\n1700000000,{},1,0.2,{},0,0,true\n",
+                u64::MAX,
+                cell
+            );
+            fs::write(dir.join(TAPS_FILE), taps).unwrap();
+            assert!(read_csv(&dir).is_err(), "cell {} should be rejected", cell);
+        }
+        let _ = fs::remove_dir_all(&dir);
+
+        let dir = test_dir("json-cells");
+        let path = dir.join(JSON_FILE);
+        for cell in ["16", "255", "300", "-1", "1.5", "\"3\"", "null"] {
+            let json = format!(
+                "{{\"rounds\": [{{\"timestamp\": 1, \"mode\": \"Classic\", \"seed\": 1, \"score\": 1, \
+                 \"length\": 1, \"scale\": 1, \"mode_duration\": 1, \"tile_lifetime\": 0, \"expired\": 0, \
+                 \"taps\": [{{\"time\": 1, \"reaction\": 0.2, \"cell\": {}, \"offset_x\": 0, \"offset_y\": 0, \
+                 \"hit\": true}}]}}]}}",
+                cell
+            );
+            fs::write(&path, &json).unwrap();
+            assert!(read_json(&path).is_err(), "cell {} should be rejected", cell);
+            fs::write(&path, json.replace(&format!("\"cell\": {}", cell), "\"cell\": 15")).unwrap();
+            assert!(read_json(&path).is_ok());
+        }
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn rejects_malformed_files() {
+        let dir = test_dir("malformed");
+        let path = dir.join(JSON_FILE);
+        for json in ["{\"rounds\": [", "{\"rounds\": [{}]}", "{\"highscores\": [{\"mode\": 1}]}"] {
+            fs::write(&path, json).unwrap();
+            assert!(read_json(&path).is_err(), "{} should be rejected", json);
+        }
+        fs::write(dir.join(ROUNDS_FILE), "header\n1,Classic,1\n").unwrap();
+        assert!(read_csv(&dir).is_err());
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,151 @@
+use macroquad::prelude::*;
+
+use super::layout::px;
+use super::pattern::CELLS;
+
+// Seconds each effect lasts
+const POP_TIME: f64 = 0.2;
+const POPUP_TIME: f64 = 0.7;
+const SHAKE_TIME: f64 = 0.3;
+const FLASH_TIME: f64 = 0.25;
+// How much larger a hit cell grows, relative to its size
+pub const POP_SCALE: f32 = 0.2;
+// Reference pixels
+const SHAKE_DISTANCE: f32 = 12.0;
+const POPUP_RISE: f32 = 60.0;
+const FLASH_COLOR: Color = color_u8!(220, 30, 30, 90);
+
+struct Popup {
+    text: String,
+    cell: usize,
+    started: f64,
+}
+
+// Tweened effects for hits and misses. Nothing is shown when disabled, for competitive play.
+pub struct Feedback {
+    pub enabled: bool,
+    // Only the red flash is shown, nothing moves
+    pub reduced_motion: bool,
+    // When each cell was last hit
+    hit_at: [f64; CELLS],
+    popups: Vec<Popup>,
+    missed_at: f64,
+}
+
+impl Feedback {
+    pub fn new() -> Self {
+        return Self {
+            ..Default::default()
+        };
+    }
+
+    pub fn clear(&mut self) {
+        self.hit_at = [f64::NEG_INFINITY; CELLS];
+        self.popups = vec![];
+        self.missed_at = f64::NEG_INFINITY;
+    }
+
+    pub fn hit(&mut self, cell: usize, points: u32, multiplier: u16) {
+        if !self.enabled || self.reduced_motion {
+            return;
+        }
+
+        let now = get_time();
+        self.hit_at[cell] = now;
+        let text = if multiplier > 1 {
+            format!("+{} x{}", points, multiplier)
+        } else {
+            format!("+{}", points)
+        };
+        self.popups.push(Popup {
+            text,
+            cell,
+            started: now,
+        });
+    }
+
+    pub fn miss(&mut self) {
+        if self.enabled {
+            self.missed_at = get_time();
+        }
+    }
+
+    // 0 to 1 through an effect of `length` seconds started at `started`, None once it's over
+    fn progress(started: f64, length: f64) -> Option<f32> {
+        let t = (get_time() - started) / length;
+        if t < 0.0 || t >= 1.0 {
+            return None;
+        }
+
+        return Some(t as f32);
+    }
+
+    // How far the cell has popped out after a hit, 1 right away easing back to 0
+    pub fn pop(&self, cell: usize) -> Option<f32> {
+        return Feedback::progress(self.hit_at[cell], POP_TIME).map(|t| 1.0 - ease_out(t));
+    }
+
+    // Screen pixels to move the board by, shaking harder right after a miss
+    pub fn shake(&self) -> Vec2 {
+        if self.reduced_motion {
+            return vec2(0.0, 0.0);
+        }
+
+        return match Feedback::progress(self.missed_at, SHAKE_TIME) {
+            Some(t) => {
+                let distance = px(SHAKE_DISTANCE) * (1.0 - t);
+                vec2((t * 40.0).sin(), (t * 31.0).cos()) * distance
+            }
+            None => vec2(0.0, 0.0),
+        };
+    }
+
+    // Whether a miss is still being shown
+    pub fn is_shaking(&self) -> bool {
+        return Feedback::progress(self.missed_at, SHAKE_TIME).is_some() && !self.reduced_motion;
+    }
+
+    // Drops the popups that are done
+    pub fn update(&mut self) {
+        self.popups
+            .retain(|popup| Feedback::progress(popup.started, POPUP_TIME).is_some());
+    }
+
+    // Popups still showing as (text, cell, how far they have risen in screen pixels, alpha)
+    pub fn popups(&self) -> Vec<(&str, usize, f32, f32)> {
+        return self
+            .popups
+            .iter()
+            .filter_map(|popup| {
+                let t = Feedback::progress(popup.started, POPUP_TIME)?;
+                Some((popup.text.as_str(), popup.cell, px(POPUP_RISE) * ease_out(t), 1.0 - t * t))
+            })
+            .collect();
+    }
+
+    // The screen turns red for a moment after a miss
+    pub fn render_flash(&self) {
+        if let Some(t) = Feedback::progress(self.missed_at, FLASH_TIME) {
+            let mut color = FLASH_COLOR;
+            color.a *= 1.0 - t;
+            draw_rectangle(0.0, 0.0, screen_width(), screen_height(), color);
+        }
+    }
+}
+
+impl Default for Feedback {
+    fn default() -> Feedback {
+        return Feedback {
+            enabled: true,
+            reduced_motion: false,
+            hit_at: [f64::NEG_INFINITY; CELLS],
+            popups: vec![],
+            missed_at: f64::NEG_INFINITY,
+        };
+    }
+}
+
+// Cubic ease out, fast at first and slowing down towards 1
+fn ease_out(t: f32) -> f32 {
+    return 1.0 - (1.0 - t).powi(3);
+}
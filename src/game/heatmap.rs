@@ -0,0 +1,143 @@
+use macroquad::prelude::*;
+
+use super::layout::px;
+use super::pattern::{Tap, CELLS};
+use super::text::{self, TextStyle};
+
+// Every cell is split into BINS x BINS squares
+const BINS: usize = 5;
+const SIDE: usize = BINS * 4;
+
+const COLD_COLOR: Color = color_u8!(40, 60, 200, 255);
+const HOT_COLOR: Color = color_u8!(255, 60, 30, 255);
+const CELL_COLOR: Color = color_u8!(20, 20, 20, 200);
+
+// Where taps land on the board, and how fast each cell is hit
+pub struct Heatmap {
+    counts: Vec<u32>,
+    misses: Vec<(u8, f32, f32)>,
+    reaction_sums: [f32; CELLS],
+    hits: [u32; CELLS],
+}
+
+impl Heatmap {
+    pub fn new() -> Self {
+        return Self {
+            ..Default::default()
+        };
+    }
+
+    pub fn from_taps<'a>(taps: impl Iterator<Item = &'a Tap>) -> Self {
+        let mut heatmap = Heatmap::new();
+        for tap in taps {
+            heatmap.add(tap);
+        }
+
+        return heatmap;
+    }
+
+    pub fn add(&mut self, tap: &Tap) {
+        let cell = tap.cell as usize;
+        let bin = |offset: f32| ((offset + 0.5) * BINS as f32).clamp(0.0, BINS as f32 - 1.0) as usize;
+        let x = (cell % 4) * BINS + bin(tap.offset_x);
+        let y = (cell / 4) * BINS + bin(tap.offset_y);
+        self.counts[y * SIDE + x] += 1;
+
+        if tap.hit {
+            self.reaction_sums[cell] += tap.reaction;
+            self.hits[cell] += 1;
+        } else {
+            self.misses.push((tap.cell, tap.offset_x, tap.offset_y));
+        }
+    }
+
+    fn mean_reaction(&self, cell: usize) -> Option<f32> {
+        if self.hits[cell] == 0 {
+            return None;
+        }
+
+        return Some(self.reaction_sums[cell] / self.hits[cell] as f32);
+    }
+
+    // Draws the board as a square with its top left corner at (x, y)
+    pub fn render(&self, x: f32, y: f32, size: f32) {
+        let cell_size = size / 4.0;
+        let bin_size = size / SIDE as f32;
+        let max = self.counts.iter().cloned().max().unwrap_or(0).max(1) as f32;
+
+        for cell in 0..CELLS {
+            let cell_x = x + (cell % 4) as f32 * cell_size;
+            let cell_y = y + (cell / 4) as f32 * cell_size;
+            draw_rectangle(cell_x, cell_y, cell_size, cell_size, CELL_COLOR);
+        }
+
+        for (i, count) in self.counts.iter().enumerate() {
+            if *count == 0 {
+                continue;
+            }
+
+            let heat = *count as f32 / max;
+            let color = Color::new(
+                COLD_COLOR.r + (HOT_COLOR.r - COLD_COLOR.r) * heat,
+                COLD_COLOR.g + (HOT_COLOR.g - COLD_COLOR.g) * heat,
+                COLD_COLOR.b + (HOT_COLOR.b - COLD_COLOR.b) * heat,
+                0.35 + 0.65 * heat,
+            );
+            draw_rectangle(
+                x + (i % SIDE) as f32 * bin_size,
+                y + (i / SIDE) as f32 * bin_size,
+                bin_size,
+                bin_size,
+                color,
+            );
+        }
+
+        for (cell, offset_x, offset_y) in &self.misses {
+            let cell = *cell as usize;
+            let miss_x = x + ((cell % 4) as f32 + 0.5 + offset_x) * cell_size;
+            let miss_y = y + ((cell / 4) as f32 + 0.5 + offset_y) * cell_size;
+            let (arm, width) = (px(4.0), px(2.0));
+            draw_line(miss_x - arm, miss_y - arm, miss_x + arm, miss_y + arm, width, RED);
+            draw_line(miss_x - arm, miss_y + arm, miss_x + arm, miss_y - arm, width, RED);
+        }
+
+        // Mean reaction per cell, the slowest cell is outlined
+        let slowest = (0..CELLS)
+            .filter_map(|cell| self.mean_reaction(cell).map(|reaction| (cell, reaction)))
+            .fold(None, |slowest: Option<(usize, f32)>, (cell, reaction)| match slowest {
+                Some((_, slowest_reaction)) if slowest_reaction >= reaction => slowest,
+                _ => Some((cell, reaction)),
+            });
+        for cell in 0..CELLS {
+            let cell_x = x + (cell % 4) as f32 * cell_size;
+            let cell_y = y + (cell / 4) as f32 * cell_size;
+            draw_rectangle_lines(cell_x, cell_y, cell_size, cell_size, px(1.0), GRAY);
+
+            if let Some(reaction) = self.mean_reaction(cell) {
+                draw_text_ex(
+                    &format!("{:.0}", reaction * 1000.0),
+                    cell_x + px(4.0),
+                    cell_y + px(14.0),
+                    text::params(TextStyle::Label),
+                );
+            }
+        }
+
+        if let Some((cell, _)) = slowest {
+            let cell_x = x + (cell % 4) as f32 * cell_size;
+            let cell_y = y + (cell / 4) as f32 * cell_size;
+            draw_rectangle_lines(cell_x, cell_y, cell_size, cell_size, px(3.0), RED);
+        }
+    }
+}
+
+impl Default for Heatmap {
+    fn default() -> Heatmap {
+        return Heatmap {
+            counts: vec![0; SIDE * SIDE],
+            misses: vec![],
+            reaction_sums: [0.0; CELLS],
+            hits: [0; CELLS],
+        };
+    }
+}
@@ -1,9 +1,36 @@
 use macroquad::prelude::*;
 
-pub struct Highscore {
+use super::layout::{at, draw_text_centred, Anchor};
+use super::locale::tr;
+use super::menu::{is_back_pressed, Button, Switcher};
+use super::mode::CLASSIC;
+use super::achievements::Achievements;
+use super::history::History;
+use super::saves::load_profiles;
+use super::text::{self, TextStyle};
+
+#[derive(Savefile, Clone)]
+pub struct HighscoreTable {
+    pub mode: String,
     pub scores: Vec<u32>,
 }
 
+impl HighscoreTable {
+    pub fn new(mode: &str) -> Self {
+        return Self {
+            mode: mode.to_owned(),
+            scores: vec![0; 9],
+        };
+    }
+}
+
+pub struct Highscore {
+    pub tables: Vec<HighscoreTable>,
+    selected: usize,
+    switcher: Switcher,
+    back: Button,
+}
+
 impl Highscore {
     pub fn new() -> Self {
         return Self {
@@ -11,53 +38,115 @@ impl Highscore {
         };
     }
 
-    pub fn setup(&mut self) {}
+    pub fn setup(&mut self) {
+        self.selected = 0;
+        self.back.label = tr("button.back");
+    }
+
+    // Makes sure every mode has a table, in the order the modes are listed
+    pub fn add_modes(&mut self, modes: &[&str]) {
+        for mode in modes {
+            self.table(mode);
+        }
+    }
+
+    fn table(&mut self, mode: &str) -> &mut HighscoreTable {
+        if let Some(i) = self.tables.iter().position(|table| table.mode == mode) {
+            return &mut self.tables[i];
+        }
 
-    pub fn score_exist(&mut self, score: u32) -> bool {
-        return self.scores.iter().find(|&&val| val == score) == Some(&score);
+        self.tables.push(HighscoreTable::new(mode));
+        return self.tables.last_mut().unwrap();
     }
 
-    pub fn add_score(&mut self, score: u32) {
+    pub fn best(&mut self, mode: &str) -> u32 {
+        return self.table(mode).scores[0];
+    }
+
+    pub fn score_exist(&mut self, mode: &str, score: u32) -> bool {
+        let scores = &self.table(mode).scores;
+        return scores.iter().find(|&&val| val == score) == Some(&score);
+    }
+
+    pub fn add_score(&mut self, mode: &str, score: u32) {
+        let scores = &mut self.table(mode).scores;
         let mut last_changed_score = score;
-        for i in 0..9 {
-            if last_changed_score > self.scores[i] {
-                let temp = self.scores[i];
-                self.scores[i] = last_changed_score;
-                last_changed_score = temp;
-            } else if last_changed_score == self.scores[i] {
+        for slot in scores.iter_mut() {
+            if last_changed_score > *slot {
+                std::mem::swap(slot, &mut last_changed_score);
+            } else if last_changed_score == *slot {
                 return;
             }
         }
     }
 
-    pub fn update(&mut self) {}
-
-    pub fn render(&mut self, font: Font) {
-        let title = "Highscore";
-        let title_dimensions = measure_text(title, Some(font), 78, 1.0);
-        draw_text_ex(
-            title,
-            screen_width() / 2.0 - title_dimensions.width / 2.0,
-            screen_height() / 2.0 - 250.0,
-            TextParams {
-                font,
-                font_size: 78,
-                color: WHITE,
-                ..Default::default()
-            },
-        );
-
-        let xy: (f32, f32) = (250.0, screen_height() / 2.0 - 150.0);
+    // Position on the table starting at 1, None if the score didn't make it
+    pub fn rank(&mut self, mode: &str, score: u32) -> Option<usize> {
+        if score == 0 {
+            return None;
+        }
+
+        let scores = &self.table(mode).scores;
+        return scores.iter().position(|&val| val == score).map(|i| i + 1);
+    }
+
+    // `squaretap highscores`, prints every table of the profile played last without opening the game
+    pub fn print_command() {
+        let profiles = load_profiles();
+        let mut highscore = Highscore::new();
+        profiles.load_active(&mut highscore, &mut History::new(), &mut Achievements::new());
+        println!("Profile: {}", profiles.active().name);
+        if highscore.tables.is_empty() {
+            println!("No highscores yet");
+        }
+
+        for table in &highscore.tables {
+            println!("{}", table.mode);
+            for (i, score) in table.scores.iter().enumerate().filter(|(_, &score)| score > 0) {
+                println!("{:>3}:{: >10}", i + 1, score);
+            }
+        }
+    }
+
+    // True when the screen should be left
+    pub fn update(&mut self) -> bool {
+        if is_back_pressed() || self.back.clicked() {
+            return true;
+        }
+
+        let count = self.tables.len();
+        if count > 0 {
+            match self.switcher.update() {
+                -1 => self.selected = (self.selected + count - 1) % count,
+                1 => self.selected = (self.selected + 1) % count,
+                _ => {}
+            }
+        }
+
+        return false;
+    }
+
+    pub fn render(&mut self) {
+        self.back.render_back();
+
+        let title = &tr("highscore.title");
+        let title_position = at(Anchor::Centre, 0.0, -250.0);
+        draw_text_centred(title, title_position.x, title_position.y, text::params(TextStyle::Title));
+
+        let table = match self.tables.get(self.selected) {
+            Some(table) => table,
+            None => return,
+        };
+
+        self.switcher.render(&table.mode, -200.0, TextStyle::MenuItem);
+
         for i in 0..9 {
+            let position = at(Anchor::Left, 250.0, -150.0 + i as f32 * 50.0);
             draw_text_ex(
-                &*format!("{}:{: >10}", i + 1, self.scores[i]),
-                xy.0,
-                xy.1 + (i as f32 * 50.0),
-                TextParams {
-                    font,
-                    font_size: 32,
-                    ..Default::default()
-                },
+                &format!("{}:{: >10}", i + 1, table.scores[i]),
+                position.x,
+                position.y,
+                text::params(TextStyle::MenuItem),
             );
         }
     }
@@ -65,6 +154,11 @@ impl Highscore {
 
 impl Default for Highscore {
     fn default() -> Highscore {
-        return Highscore { scores: vec![0; 9] };
+        return Highscore {
+            tables: vec![HighscoreTable::new(CLASSIC)],
+            selected: 0,
+            switcher: Switcher::new(),
+            back: Button::new(&tr("button.back")),
+        };
     }
 }
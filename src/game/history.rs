@@ -0,0 +1,97 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::mode::{highscore_mode, is_assisted};
+use super::pattern::{Pattern, Tap};
+
+// Everything about a finished round, kept so progress can be followed over time
+#[derive(Savefile, Clone, Debug, PartialEq)]
+pub struct RoundRecord {
+    // Seconds since the unix epoch when the round ended
+    pub timestamp: u64,
+    pub mode: String,
+    pub seed: u64,
+    pub score: u32,
+    // Seconds the round lasted
+    pub length: f32,
+    // Settings the round was played with
+    pub scale: f32,
+    pub mode_duration: f32,
+    pub tile_lifetime: f32,
+    pub expired: u32,
+    pub taps: Vec<Tap>,
+    // Assists the round was played with, rounds saved before them had none
+    #[savefile_versions = "5.."]
+    #[savefile_default_val = "1.0"]
+    pub timer_scale: f32,
+    #[savefile_versions = "5.."]
+    pub miss_tolerance: u32,
+}
+
+impl RoundRecord {
+    pub fn from_pattern(pattern: &Pattern) -> Self {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_secs());
+
+        return Self {
+            timestamp,
+            mode: pattern.mode.name.clone(),
+            seed: pattern.seed,
+            score: pattern.score,
+            length: pattern.elapsed(),
+            scale: pattern.scale,
+            mode_duration: pattern.duration(),
+            tile_lifetime: pattern.mode.tile_lifetime,
+            expired: pattern.expired,
+            taps: pattern.taps.clone(),
+            timer_scale: pattern.timer_scale,
+            miss_tolerance: pattern.miss_tolerance,
+        };
+    }
+
+    pub fn assisted(&self) -> bool {
+        return is_assisted(self.timer_scale, self.miss_tolerance);
+    }
+
+    // The mode with "(assisted)" for rounds played with assists, like their highscore table
+    pub fn highscore_mode(&self) -> String {
+        return highscore_mode(&self.mode, self.timer_scale, self.miss_tolerance);
+    }
+}
+
+pub struct History {
+    pub rounds: Vec<RoundRecord>,
+}
+
+impl History {
+    pub fn new() -> Self {
+        return Self {
+            ..Default::default()
+        };
+    }
+
+    pub fn add(&mut self, record: RoundRecord) {
+        self.rounds.push(record);
+    }
+
+    // Rounds are the same if they ended at the same time with the same seed, mode and score
+    pub fn contains(&self, record: &RoundRecord) -> bool {
+        return self.rounds.iter().any(|round| {
+            return round.timestamp == record.timestamp
+                && round.seed == record.seed
+                && round.mode == record.mode
+                && round.score == record.score;
+        });
+    }
+
+    // Rounds of one highscore mode, so assisted rounds are apart from the others, oldest first
+    pub fn rounds_for<'a>(&'a self, mode: &'a str) -> impl Iterator<Item = &'a RoundRecord> {
+        return self.rounds.iter().filter(move |round| round.highscore_mode() == mode);
+    }
+}
+
+impl Default for History {
+    fn default() -> History {
+        return History { rounds: vec![] };
+    }
+}
@@ -0,0 +1,103 @@
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+
+use macroquad::prelude::*;
+
+use serde_derive::Deserialize;
+
+// Seconds Select has to be held with hold to confirm
+pub const HOLD_TIME: f32 = 0.6;
+
+// The keyboard_layout and hold_to_confirm settings
+static KEYBOARD_LAYOUT: AtomicU8 = AtomicU8::new(0);
+static HOLD_TO_CONFIRM: AtomicBool = AtomicBool::new(false);
+
+// Extra keys on top of the default ones, so the menus can be used with one hand
+#[derive(Deserialize, Clone, Copy, PartialEq)]
+pub enum KeyboardLayout {
+    Default,
+    // WASD to move, Space to select, Q to go back
+    LeftHand,
+    // The number pad to move, 5 to select, 0 or Backspace to go back
+    RightHand,
+}
+
+impl KeyboardLayout {
+    pub const ALL: [KeyboardLayout; 3] = [
+        KeyboardLayout::Default,
+        KeyboardLayout::LeftHand,
+        KeyboardLayout::RightHand,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            KeyboardLayout::Default => return "Default",
+            KeyboardLayout::LeftHand => return "LeftHand",
+            KeyboardLayout::RightHand => return "RightHand",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Action {
+    Up,
+    Down,
+    Left,
+    Right,
+    Select,
+    // Also pauses a round
+    Back,
+}
+
+pub fn set_keyboard_layout(layout: KeyboardLayout) {
+    let index = KeyboardLayout::ALL.iter().position(|&l| l == layout).unwrap_or(0);
+    KEYBOARD_LAYOUT.store(index as u8, Ordering::Relaxed);
+}
+
+fn keyboard_layout() -> KeyboardLayout {
+    return KeyboardLayout::ALL[KEYBOARD_LAYOUT.load(Ordering::Relaxed) as usize];
+}
+
+pub fn set_hold_to_confirm(hold: bool) {
+    HOLD_TO_CONFIRM.store(hold, Ordering::Relaxed);
+}
+
+pub fn hold_to_confirm() -> bool {
+    return HOLD_TO_CONFIRM.load(Ordering::Relaxed);
+}
+
+fn keys(action: Action) -> Vec<KeyCode> {
+    let mut keys = match action {
+        Action::Up => vec![KeyCode::Up],
+        Action::Down => vec![KeyCode::Down],
+        Action::Left => vec![KeyCode::Left],
+        Action::Right => vec![KeyCode::Right],
+        Action::Select => vec![KeyCode::Enter, KeyCode::KpEnter],
+        Action::Back => vec![KeyCode::Escape],
+    };
+
+    match (keyboard_layout(), action) {
+        (KeyboardLayout::LeftHand, Action::Up) => keys.push(KeyCode::W),
+        (KeyboardLayout::LeftHand, Action::Down) => keys.push(KeyCode::S),
+        (KeyboardLayout::LeftHand, Action::Left) => keys.push(KeyCode::A),
+        (KeyboardLayout::LeftHand, Action::Right) => keys.push(KeyCode::D),
+        (KeyboardLayout::LeftHand, Action::Select) => keys.push(KeyCode::Space),
+        (KeyboardLayout::LeftHand, Action::Back) => keys.push(KeyCode::Q),
+        (KeyboardLayout::RightHand, Action::Up) => keys.push(KeyCode::Kp8),
+        (KeyboardLayout::RightHand, Action::Down) => keys.push(KeyCode::Kp2),
+        (KeyboardLayout::RightHand, Action::Left) => keys.push(KeyCode::Kp4),
+        (KeyboardLayout::RightHand, Action::Right) => keys.push(KeyCode::Kp6),
+        (KeyboardLayout::RightHand, Action::Select) => keys.push(KeyCode::Kp5),
+        (KeyboardLayout::RightHand, Action::Back) => keys.extend([KeyCode::Kp0, KeyCode::Backspace]),
+        (KeyboardLayout::Default, _) => {}
+    }
+
+    return keys;
+}
+
+pub fn is_action_pressed(action: Action) -> bool {
+    return keys(action).into_iter().any(is_key_pressed);
+}
+
+pub fn is_action_down(action: Action) -> bool {
+    return keys(action).into_iter().any(is_key_down);
+}
@@ -0,0 +1,354 @@
+use std::{fmt, iter::Peekable, str::Chars};
+
+// Arrays and objects nested deeper than this are rejected, exports have 5 levels
+const MAX_DEPTH: usize = 32;
+
+// Just enough JSON for exporting and importing game data.
+// Numbers keep their text so u64 seeds and timestamps survive the round trip.
+#[derive(Debug, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(String),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    pub fn number<T: ToString>(value: T) -> Json {
+        return Json::Number(value.to_string());
+    }
+
+    pub fn string(value: &str) -> Json {
+        return Json::String(value.to_owned());
+    }
+
+    pub fn parse(text: &str) -> Result<Json, String> {
+        let mut chars = text.chars().peekable();
+        let value = parse_value(&mut chars, 0)?;
+
+        skip_whitespace(&mut chars);
+        if let Some(c) = chars.next() {
+            return Err(format!("unexpected '{}' after the end of the document", c));
+        }
+
+        return Ok(value);
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(fields) => {
+                return fields.iter().find(|(name, _)| name == key).map(|(_, value)| value)
+            }
+            _ => return None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&Vec<Json>> {
+        match self {
+            Json::Array(values) => return Some(values),
+            _ => return None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::String(value) => return Some(value),
+            _ => return None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Json::Bool(value) => return Some(*value),
+            _ => return None,
+        }
+    }
+
+    pub fn parse_number<T: std::str::FromStr>(&self) -> Option<T> {
+        match self {
+            Json::Number(value) => return value.parse().ok(),
+            _ => return None,
+        }
+    }
+}
+
+impl fmt::Display for Json {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Json::Null => return write!(f, "null"),
+            Json::Bool(value) => return write!(f, "{}", value),
+            Json::Number(value) => return write!(f, "{}", value),
+            Json::String(value) => return write_string(f, value),
+            Json::Array(values) => {
+                write!(f, "[")?;
+                for (i, value) in values.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}", value)?;
+                }
+                return write!(f, "]");
+            }
+            Json::Object(fields) => {
+                write!(f, "{{")?;
+                for (i, (name, value)) in fields.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write_string(f, name)?;
+                    write!(f, ":{}", value)?;
+                }
+                return write!(f, "}}");
+            }
+        }
+    }
+}
+
+fn write_string(f: &mut fmt::Formatter, value: &str) -> fmt::Result {
+    write!(f, "\"")?;
+    for c in value.chars() {
+        match c {
+            '"' => write!(f, "\\\"")?,
+            '\\' => write!(f, "\\\\")?,
+            '\n' => write!(f, "\\n")?,
+            '\r' => write!(f, "\\r")?,
+            '\t' => write!(f, "\\t")?,
+            c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+            c => write!(f, "{}", c)?,
+        }
+    }
+    return write!(f, "\"");
+}
+
+fn skip_whitespace(chars: &mut Peekable<Chars>) {
+    while let Some(c) = chars.peek() {
+        if !c.is_whitespace() {
+            return;
+        }
+        chars.next();
+    }
+}
+
+fn expect(chars: &mut Peekable<Chars>, expected: &str) -> Result<(), String> {
+    for c in expected.chars() {
+        if chars.next() != Some(c) {
+            return Err(format!("expected '{}'", expected));
+        }
+    }
+
+    return Ok(());
+}
+
+// `depth` is how many arrays and objects the value is in
+fn parse_value(chars: &mut Peekable<Chars>, depth: usize) -> Result<Json, String> {
+    skip_whitespace(chars);
+    if matches!(chars.peek(), Some('[') | Some('{')) && depth >= MAX_DEPTH {
+        return Err(format!("nested more than {} deep", MAX_DEPTH));
+    }
+    match chars.peek() {
+        Some('n') => {
+            expect(chars, "null")?;
+            return Ok(Json::Null);
+        }
+        Some('t') => {
+            expect(chars, "true")?;
+            return Ok(Json::Bool(true));
+        }
+        Some('f') => {
+            expect(chars, "false")?;
+            return Ok(Json::Bool(false));
+        }
+        Some('"') => return Ok(Json::String(parse_string(chars)?)),
+        Some('[') => {
+            chars.next();
+            let mut values = vec![];
+            skip_whitespace(chars);
+            if chars.peek() == Some(&']') {
+                chars.next();
+                return Ok(Json::Array(values));
+            }
+
+            loop {
+                values.push(parse_value(chars, depth + 1)?);
+                skip_whitespace(chars);
+                match chars.next() {
+                    Some(',') => continue,
+                    Some(']') => return Ok(Json::Array(values)),
+                    _ => return Err("expected ',' or ']' in array".to_owned()),
+                }
+            }
+        }
+        Some('{') => {
+            chars.next();
+            let mut fields = vec![];
+            skip_whitespace(chars);
+            if chars.peek() == Some(&'}') {
+                chars.next();
+                return Ok(Json::Object(fields));
+            }
+
+            loop {
+                skip_whitespace(chars);
+                let name = parse_string(chars)?;
+                skip_whitespace(chars);
+                expect(chars, ":")?;
+                fields.push((name, parse_value(chars, depth + 1)?));
+                skip_whitespace(chars);
+                match chars.next() {
+                    Some(',') => continue,
+                    Some('}') => return Ok(Json::Object(fields)),
+                    _ => return Err("expected ',' or '}' in object".to_owned()),
+                }
+            }
+        }
+        Some(c) if *c == '-' || c.is_ascii_digit() => {
+            let mut number = String::new();
+            while let Some(&c) = chars.peek() {
+                if !(c.is_ascii_digit() || "+-.eE".contains(c)) {
+                    break;
+                }
+                number.push(c);
+                chars.next();
+            }
+            return Ok(Json::Number(number));
+        }
+        Some(c) => return Err(format!("unexpected '{}'", c)),
+        None => return Err("unexpected end of document".to_owned()),
+    }
+}
+
+fn parse_string(chars: &mut Peekable<Chars>) -> Result<String, String> {
+    expect(chars, "\"")?;
+
+    let mut value = String::new();
+    loop {
+        match chars.next() {
+            Some('"') => return Ok(value),
+            Some('\\') => match chars.next() {
+                Some('n') => value.push('\n'),
+                Some('r') => value.push('\r'),
+                Some('t') => value.push('\t'),
+                Some('b') => value.push('\u{8}'),
+                Some('f') => value.push('\u{c}'),
+                Some('u') => {
+                    let code: String = chars.take(4).collect();
+                    let code = u32::from_str_radix(&code, 16)
+                        .map_err(|_| format!("invalid escape \\u{}", code))?;
+                    value.push(std::char::from_u32(code).unwrap_or('\u{fffd}'));
+                }
+                Some(c) => value.push(c),
+                None => return Err("unexpected end of string".to_owned()),
+            },
+            Some(c) => value.push(c),
+            None => return Err("unexpected end of string".to_owned()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(json: Json) {
+        let text = json.to_string();
+        assert_eq!(Json::parse(&text), Ok(json), "{}", text);
+    }
+
+    #[test]
+    fn round_trips_values() {
+        round_trip(Json::Null);
+        round_trip(Json::Bool(true));
+        round_trip(Json::number(18446744073709551615u64));
+        round_trip(Json::number(-0.125f32));
+        round_trip(Json::Array(vec![]));
+        round_trip(Json::Object(vec![]));
+    }
+
+    #[test]
+    fn round_trips_escapes() {
+        round_trip(Json::string("quote \" backslash \\ slash /"));
+        round_trip(Json::string("new\nline\rreturn\ttab \u{1} \u{1f} end"));
+        round_trip(Json::string("ünïcödé ✓ 😀"));
+        round_trip(Json::Object(vec![("key \"with\" escapes\n".to_owned(), Json::Null)]));
+    }
+
+    #[test]
+    fn round_trips_nesting() {
+        round_trip(Json::Object(vec![
+            (
+                "rounds".to_owned(),
+                Json::Array(vec![
+                    Json::Object(vec![
+                        ("mode".to_owned(), Json::string("Classic")),
+                        ("taps".to_owned(), Json::Array(vec![Json::Array(vec![Json::number(1)])])),
+                    ]),
+                    Json::Array(vec![Json::Bool(false), Json::Null, Json::Object(vec![])]),
+                ]),
+            ),
+            ("empty".to_owned(), Json::Array(vec![])),
+        ]));
+    }
+
+    #[test]
+    fn parses_whitespace_and_unicode_escapes() {
+        let json = Json::parse(" { \"a\" : [ 1 , \"\\u00e9\\u0041\" ] , \"b\" : { } } ").unwrap();
+        assert_eq!(
+            json,
+            Json::Object(vec![
+                (
+                    "a".to_owned(),
+                    Json::Array(vec![Json::number(1), Json::string("éA")])
+                ),
+                ("b".to_owned(), Json::Object(vec![])),
+            ])
+        );
+        assert_eq!(json.get("a").and_then(Json::as_array).map(Vec::len), Some(2));
+    }
+
+    #[test]
+    fn rejects_malformed_documents() {
+        let malformed = [
+            "",
+            "   ",
+            "nul",
+            "tru",
+            "[1, 2",
+            "[1 2]",
+            "[1,]",
+            "{\"a\" 1}",
+            "{\"a\": 1,}",
+            "{a: 1}",
+            "{\"a\": 1",
+            "\"unterminated",
+            "\"bad escape \\u12\"",
+            "\"\\uzzzz\"",
+            "\"ends in an escape \\",
+            "[] []",
+            "@",
+        ];
+        for text in malformed {
+            assert!(Json::parse(text).is_err(), "{:?} should be an error", text);
+        }
+    }
+
+    #[test]
+    fn limits_nesting() {
+        let nested = |depth: usize| format!("{}{}", "[".repeat(depth), "]".repeat(depth));
+        assert!(Json::parse(&nested(MAX_DEPTH)).is_ok());
+        assert!(Json::parse(&nested(MAX_DEPTH + 1)).is_err());
+        assert!(Json::parse(&"{\"a\":".repeat(100_000)).is_err());
+    }
+
+    #[test]
+    fn numbers_keep_their_text() {
+        let json = Json::parse("[12345678901234567890, -1, 1.5, 300]").unwrap();
+        let numbers = json.as_array().unwrap();
+        assert_eq!(numbers[0].parse_number::<u64>(), Some(12345678901234567890));
+        assert_eq!(numbers[1].parse_number::<u8>(), None);
+        assert_eq!(numbers[2].parse_number::<u8>(), None);
+        assert_eq!(numbers[3].parse_number::<u8>(), None);
+        assert_eq!(numbers[2].parse_number::<f32>(), Some(1.5));
+    }
+}
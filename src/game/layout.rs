@@ -0,0 +1,113 @@
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+
+use macroquad::prelude::*;
+
+// Screens are laid out for this size and scaled to fit the actual one
+const REFERENCE_WIDTH: f32 = 1920.0;
+const REFERENCE_HEIGHT: f32 = 1080.0;
+// Text size with the large_text setting
+const LARGE_TEXT_SCALE: f32 = 1.3;
+// Text stays readable in small windows on high DPI screens, at the cost of some crowding
+const MIN_SCALE: f32 = 0.5;
+
+// The ui_scale setting, stored as f32 bits
+static UI_SCALE: AtomicU32 = AtomicU32::new(0x3f80_0000);
+// How much the screen's size and DPI scale reference pixels, as f32 bits. Worked out when the window
+// opens and changes size, as the DPI can't be queried cheaply on every call.
+static FIT_SCALE: AtomicU32 = AtomicU32::new(0x3f80_0000);
+static LARGE_TEXT: AtomicBool = AtomicBool::new(false);
+
+// Points on the screen positions are relative to
+#[derive(Clone, Copy)]
+pub enum Anchor {
+    TopLeft,
+    TopRight,
+    Left,
+    Centre,
+    Right,
+    BottomLeft,
+    Bottom,
+}
+
+pub fn set_ui_scale(ui_scale: f32) {
+    UI_SCALE.store(ui_scale.to_bits(), Ordering::Relaxed);
+}
+
+pub fn set_large_text(large: bool) {
+    LARGE_TEXT.store(large, Ordering::Relaxed);
+}
+
+// Has to be called once the window is open and every time its size changes
+pub fn update_scale() {
+    // SAFETY: only called from the game loop on the main thread, where macroquad's context lives,
+    // and the context is only read for the DPI while nothing else is using it
+    let dpi = unsafe { get_internal_gl().quad_context.dpi_scale() };
+    let fit = (screen_width() / REFERENCE_WIDTH).min(screen_height() / REFERENCE_HEIGHT);
+
+    FIT_SCALE.store(fit.max(MIN_SCALE * dpi).to_bits(), Ordering::Relaxed);
+}
+
+// Reference pixels to screen pixels
+pub fn scale() -> f32 {
+    let fit = f32::from_bits(FIT_SCALE.load(Ordering::Relaxed));
+    let ui_scale = f32::from_bits(UI_SCALE.load(Ordering::Relaxed));
+
+    return fit * ui_scale;
+}
+
+pub fn px(value: f32) -> f32 {
+    return value * scale();
+}
+
+// How much larger text is than the rest of the UI
+pub fn text_scale() -> f32 {
+    if LARGE_TEXT.load(Ordering::Relaxed) {
+        return LARGE_TEXT_SCALE;
+    }
+
+    return 1.0;
+}
+
+pub fn font_size(size: u16) -> u16 {
+    return (size as f32 * scale() * text_scale()).round().max(1.0) as u16;
+}
+
+// (x, y) in reference pixels from the anchor
+pub fn at(anchor: Anchor, x: f32, y: f32) -> Vec2 {
+    let (w, h) = (screen_width(), screen_height());
+    let point = match anchor {
+        Anchor::TopLeft => vec2(0.0, 0.0),
+        Anchor::TopRight => vec2(w, 0.0),
+        Anchor::Left => vec2(0.0, h / 2.0),
+        Anchor::Centre => vec2(w / 2.0, h / 2.0),
+        Anchor::Right => vec2(w, h / 2.0),
+        Anchor::BottomLeft => vec2(0.0, h),
+        Anchor::Bottom => vec2(w / 2.0, h),
+    };
+
+    return point + vec2(x, y) * scale();
+}
+
+// Draws text with its horizontal centre at `x`
+pub fn draw_text_centred(text: &str, x: f32, y: f32, params: TextParams) {
+    let dimensions = measure_text(text, Some(params.font), params.font_size, params.font_scale);
+    draw_text_ex(text, x - dimensions.width / 2.0, y, params);
+}
+
+// Scales the texture to cover the whole screen, cropping what doesn't fit
+pub fn draw_background(texture: Texture2D) {
+    let (w, h) = (screen_width(), screen_height());
+    let cover = (w / texture.width()).max(h / texture.height());
+    let size = vec2(texture.width(), texture.height()) * cover;
+
+    draw_texture_ex(
+        texture,
+        (w - size.x) / 2.0,
+        (h - size.y) / 2.0,
+        WHITE,
+        DrawTextureParams {
+            dest_size: Some(size),
+            ..Default::default()
+        },
+    );
+}
@@ -0,0 +1,238 @@
+use std::{
+    collections::HashMap,
+    fs,
+    sync::{OnceLock, RwLock},
+};
+
+use macroquad::prelude::*;
+
+use super::paths::paths;
+
+// Messages missing from a language are shown in this one
+pub const FALLBACK_LANGUAGE: &str = "en";
+const FONT: &str = "fonts/alagard.ttf";
+// alagard.ttf only has ASCII, this one has the accented letters, Greek and Cyrillic
+const FALLBACK_FONT: &str = "fonts/DejaVuSans.ttf";
+
+// The language picked in the settings, read by every screen
+static LOCALE: OnceLock<RwLock<Locale>> = OnceLock::new();
+
+struct Locale {
+    language: String,
+    messages: HashMap<String, String>,
+    fallback: HashMap<String, String>,
+    // Font the language file asks for
+    font: Option<String>,
+}
+
+// A language file in res/lang
+pub struct Language {
+    pub code: String,
+    pub name: String,
+}
+
+struct Catalog {
+    name: String,
+    font: Option<String>,
+    messages: HashMap<String, String>,
+}
+
+fn locale() -> &'static RwLock<Locale> {
+    return LOCALE.get_or_init(|| {
+        let fallback = read_catalog(FALLBACK_LANGUAGE)
+            .map(|catalog| catalog.messages)
+            .unwrap_or_default();
+        RwLock::new(Locale {
+            language: FALLBACK_LANGUAGE.to_owned(),
+            messages: fallback.clone(),
+            fallback,
+            font: None,
+        })
+    });
+}
+
+// Switches every message to `code`, staying in English if its file can't be read
+pub fn set_language(code: &str) {
+    let catalog = match read_catalog(code) {
+        Ok(catalog) => catalog,
+        Err(err) => {
+            eprintln!("{}, using English", err);
+            Catalog {
+                name: String::new(),
+                font: None,
+                messages: HashMap::new(),
+            }
+        }
+    };
+
+    let mut locale = locale().write().unwrap();
+    locale.language = code.to_owned();
+    locale.messages = catalog.messages;
+    locale.font = catalog.font;
+}
+
+pub fn language() -> String {
+    return locale().read().unwrap().language.clone();
+}
+
+// Every language in res/lang, English first and the rest by code
+pub fn languages() -> Vec<Language> {
+    let mut languages: Vec<Language> = fs::read_dir(paths().asset("lang"))
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .filter_map(|entry| {
+                    let path = entry.path();
+                    if path.extension()? != "toml" {
+                        return None;
+                    }
+                    let code = path.file_stem()?.to_string_lossy().into_owned();
+                    let name = read_catalog(&code).ok()?.name;
+                    Some(Language { code, name })
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    languages.sort_by_key(|language| (language.code != FALLBACK_LANGUAGE, language.code.clone()));
+
+    return languages;
+}
+
+// The message in the current language, in English if it's missing and the key itself if that is too
+pub fn tr(key: &str) -> String {
+    let locale = locale().read().unwrap();
+    return locale
+        .messages
+        .get(key)
+        .or_else(|| locale.fallback.get(key))
+        .cloned()
+        .unwrap_or_else(|| key.to_owned());
+}
+
+// `tr` with every {name} in the message replaced by its value
+pub fn tr_with(key: &str, args: &[(&str, String)]) -> String {
+    let mut message = tr(key);
+    for (name, value) in args {
+        message = message.replace(&format!("{{{}}}", name), value);
+    }
+
+    return message;
+}
+
+// The font to draw the current language with, the fallback font when the bundled one is missing
+// some of its characters
+pub fn load_font() -> Font {
+    let locale = locale().read().unwrap();
+    let path = match &locale.font {
+        Some(font) => font.clone(),
+        None if !covers(FONT, locale.messages.values()) => FALLBACK_FONT.to_owned(),
+        None => FONT.to_owned(),
+    };
+    drop(locale);
+
+    let font = fs::read(paths().asset(&path))
+        .map_err(|err| err.to_string())
+        .and_then(|bytes| load_ttf_font_from_bytes(&bytes).map_err(|err| format!("{:?}", err)));
+    match font {
+        Ok(font) => return font,
+        Err(err) if path != FONT => {
+            eprintln!("Couldn't load the font {}, using {}: {}", path, FONT, err);
+            let bytes = fs::read(paths().asset(FONT)).unwrap();
+            return load_ttf_font_from_bytes(&bytes).unwrap();
+        }
+        Err(err) => panic!("Couldn't load the font {}: {}", FONT, err),
+    }
+}
+
+// Whether the font has a glyph for every character of the messages
+fn covers<'a>(font: &str, mut messages: impl Iterator<Item = &'a String>) -> bool {
+    let font = match fs::read(paths().asset(font))
+        .ok()
+        .and_then(|bytes| fontdue::Font::from_bytes(bytes, fontdue::FontSettings::default()).ok())
+    {
+        Some(font) => font,
+        None => return true,
+    };
+
+    return messages.all(|message| {
+        message
+            .chars()
+            .all(|c| c.is_whitespace() || font.lookup_glyph_index(c) != 0)
+    });
+}
+
+// Messages of every language that are missing from it or don't have the same {names} as in English,
+// for `squaretap validate`
+pub fn check_languages() -> Vec<String> {
+    let english = match read_catalog(FALLBACK_LANGUAGE) {
+        Ok(catalog) => catalog.messages,
+        Err(err) => return vec![err],
+    };
+
+    let mut warnings = vec![];
+    for language in languages().iter().filter(|language| language.code != FALLBACK_LANGUAGE) {
+        let catalog = match read_catalog(&language.code) {
+            Ok(catalog) => catalog,
+            Err(err) => {
+                warnings.push(err);
+                continue;
+            }
+        };
+
+        let mut keys: Vec<&String> = english.keys().collect();
+        keys.sort();
+        for key in keys {
+            match catalog.messages.get(key) {
+                Some(message) if placeholders(message) != placeholders(&english[key]) => warnings.push(format!(
+                    "{}: \"{}\" doesn't have the same {{names}} as in English",
+                    language.code, key
+                )),
+                Some(_) => {}
+                None => warnings.push(format!("{}: \"{}\" is missing, shown in English", language.code, key)),
+            }
+        }
+    }
+
+    return warnings;
+}
+
+fn placeholders(message: &str) -> Vec<&str> {
+    let mut names: Vec<&str> = message
+        .split('{')
+        .skip(1)
+        .filter_map(|part| part.split('}').next())
+        .collect();
+    names.sort_unstable();
+
+    return names;
+}
+
+// Reads res/lang/<code>.toml, tables are flattened into "table.key" messages
+fn read_catalog(code: &str) -> Result<Catalog, String> {
+    let path = paths().asset(&format!("lang/{}.toml", code));
+    let invalid = |err: &dyn std::fmt::Display| format!("{}: {}", path.display(), err);
+    let text = fs::read_to_string(&path).map_err(|err| invalid(&err))?;
+    let table: toml::value::Table = toml::from_str(&text).map_err(|err| invalid(&err))?;
+
+    let mut catalog = Catalog {
+        name: code.to_owned(),
+        font: None,
+        messages: HashMap::new(),
+    };
+    for (key, value) in table {
+        match (key.as_str(), value) {
+            ("name", toml::Value::String(name)) => catalog.name = name,
+            ("font", toml::Value::String(font)) => catalog.font = Some(font),
+            (_, toml::Value::Table(messages)) => {
+                for (name, message) in messages {
+                    if let toml::Value::String(message) = message {
+                        catalog.messages.insert(format!("{}.{}", key, name), message);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    return Ok(catalog);
+}
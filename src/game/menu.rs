@@ -0,0 +1,401 @@
+use macroquad::prelude::*;
+
+use super::input::{hold_to_confirm, is_action_down, is_action_pressed, Action, HOLD_TIME};
+use super::layout::{at, draw_text_centred, px, Anchor};
+use super::locale::tr;
+use super::text::{self, TextStyle};
+
+// How much of the style's color shows through for items that aren't selected or can't be
+const NOT_SELECTED_ALPHA: f32 = 0.5;
+const DISABLED_ALPHA: f32 = 0.27;
+
+pub enum MenuEvent<T> {
+    // An item with an action was chosen
+    Activated(T),
+    // Escape, a right click or the back button on the top level of the menu
+    Back,
+}
+
+pub struct MenuItem<T> {
+    pub label: String,
+    pub action: Option<T>,
+    pub enabled: bool,
+    pub submenu: Option<Menu<T>>,
+}
+
+impl<T> MenuItem<T> {
+    pub fn new(label: &str, action: T) -> Self {
+        return Self {
+            label: label.to_owned(),
+            action: Some(action),
+            enabled: true,
+            submenu: None,
+        };
+    }
+
+    // Shown but can't be selected, for information in a list
+    pub fn disabled(label: &str) -> Self {
+        return Self {
+            label: label.to_owned(),
+            action: None,
+            enabled: false,
+            submenu: None,
+        };
+    }
+
+    // Opens `menu` when chosen, Escape goes back
+    pub fn submenu(label: &str, menu: Menu<T>) -> Self {
+        return Self {
+            label: label.to_owned(),
+            action: None,
+            enabled: true,
+            submenu: Some(menu),
+        };
+    }
+}
+
+// A vertical list of items chosen with Up/Down and Enter, or the mouse
+pub struct Menu<T> {
+    pub title: String,
+    pub items: Vec<MenuItem<T>>,
+    // Where the first item is drawn, in reference pixels from the left middle of the screen
+    pub left: f32,
+    pub top: f32,
+    pub spacing: f32,
+    pub style: TextStyle,
+    // Shows a back button for the mouse, sends `MenuEvent::Back` like Escape
+    pub back_button: bool,
+    back: Button,
+    selected: usize,
+    open: Option<usize>,
+    // Where the items were last drawn, for the mouse
+    rects: Vec<Rect>,
+    last_mouse: (f32, f32),
+    // Seconds Select has been held on the selected item, with hold to confirm
+    held: Option<f32>,
+}
+
+impl<T: Clone> Menu<T> {
+    pub fn new(title: &str, items: Vec<MenuItem<T>>) -> Self {
+        let mut menu = Self {
+            title: title.to_owned(),
+            items,
+            ..Default::default()
+        };
+        menu.select(0);
+
+        return menu;
+    }
+
+    pub fn selected(&self) -> usize {
+        return self.selected;
+    }
+
+    // Selects the item at `i`, or the next enabled one after it
+    pub fn select(&mut self, i: usize) {
+        let count = self.items.len();
+        self.selected = (0..count)
+            .map(|step| (i + step) % count)
+            .find(|&i| self.items[i].enabled)
+            .unwrap_or(0);
+    }
+
+    // Back to the top level with the first item selected
+    pub fn reset(&mut self) {
+        self.open = None;
+        self.held = None;
+        self.select(0);
+    }
+
+    fn step(&mut self, forward: bool) {
+        let count = self.items.len();
+        for step in 1..=count {
+            let i = if forward {
+                (self.selected + step) % count
+            } else {
+                (self.selected + count - step) % count
+            };
+            if self.items[i].enabled {
+                self.selected = i;
+                return;
+            }
+        }
+    }
+
+    fn activate(&mut self, i: usize) -> Option<MenuEvent<T>> {
+        let item = &mut self.items[i];
+        if !item.enabled {
+            return None;
+        }
+
+        if let Some(submenu) = &mut item.submenu {
+            submenu.reset();
+            self.open = Some(i);
+            return None;
+        }
+
+        return item.action.clone().map(MenuEvent::Activated);
+    }
+
+    fn hovered(&self) -> Option<usize> {
+        let mouse = mouse_position();
+        return self
+            .rects
+            .iter()
+            .position(|rect| rect.contains(vec2(mouse.0, mouse.1)))
+            .filter(|&i| self.items[i].enabled);
+    }
+
+    pub fn update(&mut self) -> Option<MenuEvent<T>> {
+        if let Some(open) = self.open {
+            let submenu = self.items[open].submenu.as_mut().unwrap();
+            match submenu.update() {
+                Some(MenuEvent::Back) => self.open = None,
+                event => return event,
+            }
+            return None;
+        }
+
+        if self.items.is_empty() {
+            return if is_back_pressed() {
+                Some(MenuEvent::Back)
+            } else {
+                None
+            };
+        }
+
+        // The mouse only takes over the selection once it moves, so it doesn't fight the keyboard
+        let mouse = mouse_position();
+        if mouse != self.last_mouse {
+            self.last_mouse = mouse;
+            if let Some(i) = self.hovered() {
+                self.selected = i;
+            }
+        }
+
+        if hold_to_confirm() {
+            return self.update_hold();
+        }
+
+        if is_back_pressed() || (self.back_button && self.back.clicked()) {
+            return Some(MenuEvent::Back);
+        } else if is_action_pressed(Action::Up) {
+            self.step(false);
+        } else if is_action_pressed(Action::Down) {
+            self.step(true);
+        } else if is_action_pressed(Action::Select) {
+            return self.activate(self.selected);
+        } else if is_mouse_button_pressed(MouseButton::Left) {
+            if let Some(i) = self.hovered() {
+                self.selected = i;
+                return self.activate(i);
+            }
+        }
+
+        return None;
+    }
+
+    // Like `update`, but Select or the mouse has to be held on an item to choose it
+    fn update_hold(&mut self) -> Option<MenuEvent<T>> {
+        if let Some(held) = self.held {
+            let on_item = self.hovered() == Some(self.selected);
+            if is_action_down(Action::Select) || (is_mouse_button_down(MouseButton::Left) && on_item) {
+                let held = held + get_frame_time();
+                if held < HOLD_TIME {
+                    self.held = Some(held);
+                    return None;
+                }
+
+                self.held = None;
+                return self.activate(self.selected);
+            }
+            self.held = None;
+        }
+
+        if is_back_pressed() || (self.back_button && self.back.clicked()) {
+            return Some(MenuEvent::Back);
+        } else if is_action_pressed(Action::Up) {
+            self.step(false);
+        } else if is_action_pressed(Action::Down) {
+            self.step(true);
+        } else if is_action_pressed(Action::Select) {
+            self.held = Some(0.0);
+        } else if is_mouse_button_pressed(MouseButton::Left) {
+            if let Some(i) = self.hovered() {
+                self.selected = i;
+                self.held = Some(0.0);
+            }
+        }
+
+        return None;
+    }
+
+    pub fn render(&mut self) {
+        if let Some(open) = self.open {
+            let item = &mut self.items[open];
+            let submenu = item.submenu.as_mut().unwrap();
+            if submenu.title.is_empty() {
+                submenu.title = item.label.clone();
+            }
+            submenu.render();
+            return;
+        }
+
+        if self.back_button {
+            self.back.render_back();
+        }
+
+        if !self.title.is_empty() {
+            let title = at(Anchor::Centre, 0.0, -250.0);
+            draw_text_centred(&self.title, title.x, title.y, text::params(TextStyle::Title));
+        }
+
+        self.rects = vec![];
+        for (i, item) in self.items.iter().enumerate() {
+            let mut params = text::params(self.style);
+            if !item.enabled {
+                params.color.a *= DISABLED_ALPHA;
+            } else {
+                params.color = highlight(params.color, i == self.selected);
+            }
+
+            let position = at(Anchor::Left, self.left, self.top + i as f32 * self.spacing);
+            self.rects.push(draw_label(&item.label, position, params));
+        }
+
+        // How long Select is left to be held, as a bar under the item
+        if let (Some(held), Some(rect)) = (self.held, self.rects.get(self.selected)) {
+            let width = rect.w * (held / HOLD_TIME).min(1.0);
+            let color = text::params(self.style).color;
+            draw_rectangle(rect.x, rect.y + rect.h + px(4.0), width, px(3.0), color);
+        }
+    }
+}
+
+impl<T> Default for Menu<T> {
+    fn default() -> Menu<T> {
+        return Menu {
+            title: String::new(),
+            items: vec![],
+            left: 250.0,
+            top: -50.0,
+            spacing: 50.0,
+            style: TextStyle::MenuItem,
+            back_button: true,
+            back: Button::new(&tr("button.back")),
+            selected: 0,
+            open: None,
+            rects: vec![],
+            last_mouse: (0.0, 0.0),
+            held: None,
+        };
+    }
+}
+
+// A single clickable label, for actions outside of a list
+pub struct Button {
+    pub label: String,
+    rect: Rect,
+}
+
+impl Button {
+    pub fn new(label: &str) -> Self {
+        return Self {
+            label: label.to_owned(),
+            rect: Rect::new(0.0, 0.0, 0.0, 0.0),
+        };
+    }
+
+    pub fn hovered(&self) -> bool {
+        let mouse = mouse_position();
+        return self.rect.contains(vec2(mouse.0, mouse.1));
+    }
+
+    pub fn clicked(&self) -> bool {
+        return is_mouse_button_pressed(MouseButton::Left) && self.hovered();
+    }
+
+    // Draws the label with its baseline at `position`, highlighted while hovered
+    pub fn render(&mut self, position: Vec2, style: TextStyle) {
+        let mut params = text::params(style);
+        params.color = highlight(params.color, self.hovered());
+        self.rect = draw_label(&self.label, position, params);
+    }
+
+    // The back button in the top left corner every screen but the main menu has
+    pub fn render_back(&mut self) {
+        self.render(at(Anchor::TopLeft, 50.0, 80.0), TextStyle::MenuItem);
+    }
+}
+
+// "< label >" centred on the screen, switched with Left/Right or by clicking the arrows
+pub struct Switcher {
+    previous: Button,
+    next: Button,
+}
+
+impl Switcher {
+    pub fn new() -> Self {
+        return Self {
+            ..Default::default()
+        };
+    }
+
+    // -1 for the previous item, 1 for the next, 0 to stay
+    pub fn update(&self) -> i32 {
+        if is_action_pressed(Action::Left) || self.previous.clicked() {
+            return -1;
+        } else if is_action_pressed(Action::Right) || self.next.clicked() {
+            return 1;
+        }
+
+        return 0;
+    }
+
+    pub fn render(&mut self, label: &str, y: f32, style: TextStyle) {
+        let position = at(Anchor::Centre, 0.0, y);
+        let width = text::measure(label, style).width;
+        draw_text_centred(label, position.x, position.y, text::params(style));
+
+        let gap = text::measure("< ", style).width;
+        let previous_width = text::measure(&self.previous.label, style).width;
+        self.previous.render(vec2(position.x - width / 2.0 - gap - previous_width, position.y), style);
+        self.next.render(vec2(position.x + width / 2.0 + gap, position.y), style);
+    }
+}
+
+impl Default for Switcher {
+    fn default() -> Switcher {
+        return Switcher {
+            previous: Button::new("<"),
+            next: Button::new(">"),
+        };
+    }
+}
+
+// Escape or a right click, leaves the screen
+pub fn is_back_pressed() -> bool {
+    return is_action_pressed(Action::Back) || is_mouse_button_pressed(MouseButton::Right);
+}
+
+// The style's color when selected, faded otherwise
+fn highlight(mut color: Color, selected: bool) -> Color {
+    if !selected {
+        color.a *= NOT_SELECTED_ALPHA;
+    }
+
+    return color;
+}
+
+// Draws the text and returns the area it covers
+fn draw_label(text: &str, position: Vec2, params: TextParams) -> Rect {
+    draw_text_ex(text, position.x, position.y, params);
+
+    let dimensions = measure_text(text, Some(params.font), params.font_size, params.font_scale);
+    return Rect::new(
+        position.x,
+        position.y - dimensions.offset_y,
+        dimensions.width,
+        dimensions.height.max(dimensions.offset_y),
+    );
+}
@@ -0,0 +1,190 @@
+use serde_derive::Deserialize;
+
+use super::locale::tr_with;
+use super::tile::TileWeights;
+
+#[derive(Deserialize, Clone, Copy, PartialEq)]
+pub enum TimerVariant {
+    // Counts down from the round duration
+    Fixed,
+    // Every hit adds `time_bonus` seconds
+    Bonus,
+    // Every hit adds time, but each hit adds `time_shrink` seconds less than the last
+    BeatTheClock,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct GameMode {
+    pub name: String,
+    pub duration: f32,
+    #[serde(default = "default_timer")]
+    pub timer: TimerVariant,
+    #[serde(default)]
+    pub time_bonus: f32,
+    #[serde(default)]
+    pub time_shrink: f32,
+    #[serde(default)]
+    pub time_bonus_min: f32,
+    // Name of a difficulty curve in res/difficulty.toml
+    #[serde(default)]
+    pub curve: Option<String>,
+    // Seconds a lit tile stays lit before it expires, 0 for never
+    #[serde(default)]
+    pub tile_lifetime: f32,
+    // Points lost when a tile expires
+    #[serde(default)]
+    pub expire_penalty: u32,
+    // Expired tiles that end the round, 0 for unlimited
+    #[serde(default)]
+    pub lives: u32,
+    #[serde(default)]
+    pub tile_weights: TileWeights,
+    // Beats per minute of the metronome, 0 for none. Hits score by how close to the beat they land.
+    #[serde(default)]
+    pub bpm: f32,
+}
+
+fn default_timer() -> TimerVariant {
+    return TimerVariant::Fixed;
+}
+
+pub const CLASSIC: &str = "Classic";
+
+// Rounds played with assists are kept apart from the others on the highscores, statistics and achievements
+pub fn is_assisted(timer_scale: f32, miss_tolerance: u32) -> bool {
+    return timer_scale > 1.0 || miss_tolerance > 0;
+}
+
+// Name of the highscore table and statistics a round of `mode` goes into
+pub fn highscore_mode(mode: &str, timer_scale: f32, miss_tolerance: u32) -> String {
+    if is_assisted(timer_scale, miss_tolerance) {
+        return format!("{} (assisted)", mode);
+    }
+
+    return mode.to_owned();
+}
+
+impl GameMode {
+    pub fn classic(duration: f32) -> Self {
+        return Self {
+            name: CLASSIC.to_owned(),
+            duration,
+            timer: TimerVariant::Fixed,
+            time_bonus: 0.0,
+            time_shrink: 0.0,
+            time_bonus_min: 0.0,
+            curve: None,
+            tile_lifetime: 0.0,
+            expire_penalty: 0,
+            lives: 0,
+            tile_weights: TileWeights::default(),
+            bpm: 0.0,
+        };
+    }
+
+    pub fn with_curve(name: &str, duration: f32, curve: &str) -> Self {
+        return Self {
+            name: name.to_owned(),
+            curve: Some(curve.to_owned()),
+            ..GameMode::classic(duration)
+        };
+    }
+
+    // Built in modes, Classic uses the round duration from the settings
+    pub fn builtin(round_duration: f32) -> Vec<Self> {
+        return vec![
+            GameMode::classic(round_duration),
+            GameMode {
+                name: "Time bonus".to_owned(),
+                timer: TimerVariant::Bonus,
+                time_bonus: 0.5,
+                ..GameMode::classic(15.0)
+            },
+            GameMode {
+                name: "Beat the clock".to_owned(),
+                timer: TimerVariant::BeatTheClock,
+                time_bonus: 1.5,
+                time_shrink: 0.025,
+                time_bonus_min: 0.25,
+                ..GameMode::classic(5.0)
+            },
+            GameMode::with_curve("Ramp up", 60.0, "Ramp"),
+            GameMode::with_curve("Drift", 60.0, "Drift"),
+            GameMode::with_curve("Spin", 60.0, "Spin"),
+            GameMode {
+                name: "Targets".to_owned(),
+                tile_lifetime: 1.5,
+                expire_penalty: 5,
+                lives: 3,
+                ..GameMode::with_curve("Targets", 60.0, "Targets")
+            },
+            GameMode {
+                name: "Specials".to_owned(),
+                tile_weights: TileWeights {
+                    normal: 6.0,
+                    decoy: 2.0,
+                    bonus: 1.0,
+                    time: 1.0,
+                    multi_hit: 1.0,
+                },
+                ..GameMode::classic(30.0)
+            },
+            GameMode {
+                name: "Metronome".to_owned(),
+                bpm: 100.0,
+                ..GameMode::classic(30.0)
+            },
+        ];
+    }
+
+    pub fn description(&self) -> String {
+        let mut description = self.timer_description();
+        if self.bpm > 0.0 {
+            description += &tr_with("mode.bpm", &[("bpm", self.bpm.to_string())]);
+        }
+        if self.tile_lifetime > 0.0 {
+            description += &tr_with("mode.expire", &[("lifetime", self.tile_lifetime.to_string())]);
+            if self.lives > 0 {
+                description += &tr_with("mode.lives", &[("lives", self.lives.to_string())]);
+            }
+        }
+
+        return description;
+    }
+
+    fn timer_description(&self) -> String {
+        let duration = ("duration", self.duration.to_string());
+        let bonus = ("bonus", self.time_bonus.to_string());
+        match self.timer {
+            TimerVariant::Fixed => match &self.curve {
+                Some(curve) => return tr_with("mode.round_curve", &[duration, ("curve", curve.clone())]),
+                None => return tr_with("mode.round", &[duration]),
+            },
+            TimerVariant::Bonus => return tr_with("mode.bonus", &[duration, bonus]),
+            TimerVariant::BeatTheClock => {
+                return tr_with(
+                    "mode.beat_the_clock",
+                    &[duration, bonus, ("shrink", self.time_shrink.to_string())],
+                )
+            }
+        }
+    }
+
+    // Seconds added to the timer for the n:th hit of the round, starting at 0
+    pub fn hit_bonus(&self, hits: u64) -> f32 {
+        match self.timer {
+            TimerVariant::Fixed => return 0.0,
+            TimerVariant::Bonus => return self.time_bonus,
+            TimerVariant::BeatTheClock => {
+                let bonus = self.time_bonus - self.time_shrink * hits as f32;
+                return bonus.max(self.time_bonus_min);
+            }
+        }
+    }
+}
+
+impl Default for GameMode {
+    fn default() -> GameMode {
+        return GameMode::classic(30.0);
+    }
+}
@@ -0,0 +1,86 @@
+use macroquad::prelude::*;
+
+use super::difficulty::DifficultyCurve;
+use super::layout::{at, Anchor};
+use super::locale::tr;
+use super::menu::{Menu, MenuEvent, MenuItem};
+use super::mode::GameMode;
+use super::text::{self, TextStyle};
+
+pub struct ModeSelect {
+    pub modes: Vec<GameMode>,
+    pub curves: Vec<DifficultyCurve>,
+    menu: Menu<usize>,
+}
+
+impl ModeSelect {
+    pub fn new() -> Self {
+        return Self {
+            ..Default::default()
+        };
+    }
+
+    pub fn set_modes(&mut self, modes: Vec<GameMode>, curves: Vec<DifficultyCurve>) {
+        let items = modes
+            .iter()
+            .enumerate()
+            .map(|(i, mode)| MenuItem::new(&mode.name, i))
+            .collect();
+        self.menu = Menu::new(&tr("mode_select.title"), items);
+        self.menu.top = -150.0;
+        self.modes = modes;
+        self.curves = curves;
+    }
+
+    // The title follows the language picked in the settings
+    pub fn setup(&mut self) {
+        self.menu.title = tr("mode_select.title");
+    }
+
+    pub fn selected_mode(&self) -> GameMode {
+        return self.modes[self.menu.selected()].clone();
+    }
+
+    // Selects a mode by name, false if there is no such mode
+    pub fn select(&mut self, name: &str) -> bool {
+        match self.modes.iter().position(|mode| mode.name == name) {
+            Some(i) => {
+                self.menu.select(i);
+                return true;
+            }
+            None => return false,
+        }
+    }
+
+    // Difficulty curve the selected mode refers to, None if it has none or it can't be found
+    pub fn selected_curve(&self) -> Option<DifficultyCurve> {
+        let name = self.modes[self.menu.selected()].curve.as_ref()?;
+        return self.curves.iter().find(|curve| &curve.name == name).cloned();
+    }
+
+    pub fn update(&mut self) -> Option<MenuEvent<usize>> {
+        return self.menu.update();
+    }
+
+    pub fn render(&mut self) {
+        self.menu.render();
+
+        let description = at(Anchor::Left, 250.0, -150.0 + self.modes.len() as f32 * 50.0 + 25.0);
+        draw_text_ex(
+            &self.selected_mode().description(),
+            description.x,
+            description.y,
+            text::params(TextStyle::Small),
+        );
+    }
+}
+
+impl Default for ModeSelect {
+    fn default() -> ModeSelect {
+        return ModeSelect {
+            modes: vec![GameMode::default()],
+            curves: vec![],
+            menu: Menu::default(),
+        };
+    }
+}
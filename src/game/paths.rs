@@ -0,0 +1,49 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+static PATHS: OnceLock<Paths> = OnceLock::new();
+
+// Where the settings, save file and resources are read from, set once at startup
+pub struct Paths {
+    pub config: PathBuf,
+    pub save: PathBuf,
+    pub assets: PathBuf,
+}
+
+impl Paths {
+    pub fn new() -> Self {
+        return Self {
+            ..Default::default()
+        };
+    }
+
+    pub fn asset(&self, name: &str) -> PathBuf {
+        return self.assets.join(name);
+    }
+}
+
+impl Default for Paths {
+    fn default() -> Paths {
+        return Paths {
+            config: PathBuf::from("settings.toml"),
+            save: PathBuf::from("savefile.bin"),
+            assets: PathBuf::from("res"),
+        };
+    }
+}
+
+// Has to be called before anything is loaded, later calls are ignored
+pub fn set_paths(paths: Paths) {
+    let _ = PATHS.set(paths);
+}
+
+pub fn paths() -> &'static Paths {
+    return PATHS.get_or_init(Paths::new);
+}
+
+// Macroquad loads files from string paths
+pub fn path_str(path: &Path) -> String {
+    return path.to_string_lossy().into_owned();
+}
@@ -1,29 +1,107 @@
-use ::rand::{rngs::ThreadRng, thread_rng, Rng};
+use ::rand::{rngs::StdRng, thread_rng, Rng, SeedableRng};
 use macroquad::prelude::*;
 
+use super::difficulty::{DifficultyCurve, DifficultyStep};
+use super::feedback::{Feedback, POP_SCALE};
+use super::layout::{at, px, text_scale, Anchor};
+use super::locale::{tr, tr_with};
+use super::mode::{highscore_mode, GameMode};
+use super::text::{self, TextStyle};
+use super::tile::{
+    Tile, TileKind, BONUS_MULTIPLIER, DECOY_LIFETIME, MAX_DECOYS, TIME_EXTENSION,
+};
+
+// The board is 4 x 4 cells
+pub const CELLS: usize = 16;
 const SCALE_MIN: f32 = 0.5;
 const SCALE_MAX: f32 = 3.0;
 const SCALE_CHANGE: f32 = 0.25;
+// Hits in a row without a miss or an expired tile for each combo tier
+const COMBO_TIER: u64 = 10;
+// The last seconds of a round are counted down
+const COUNTDOWN_SECONDS: f32 = 5.0;
+// Hits within these many seconds of the beat are perfect or good, anything further off only scores 1
+const BEAT_PERFECT: f32 = 0.035;
+const BEAT_GOOD: f32 = 0.08;
+// Seconds the timing of the last hit is shown
+const BEAT_LABEL_TIME: f64 = 0.5;
+
+// Things that happened during an update, for sounds
+#[derive(Clone, Copy, PartialEq)]
+pub enum PatternEvent {
+    Hit,
+    Miss,
+    ComboTier,
+    Countdown,
+    RoundEnd,
+    // A tick of the metronome
+    Beat,
+}
+
+#[derive(Savefile, Clone, Copy, Debug, PartialEq)]
+pub struct Tap {
+    // Seconds since the round started
+    pub time: f32,
+    // Seconds since the previous hit, or since the round started for the first tap
+    pub reaction: f32,
+    pub cell: u8,
+    // Position inside the cell relative to its centre, -0.5..0.5 on both axes
+    pub offset_x: f32,
+    pub offset_y: f32,
+    pub hit: bool,
+}
 
 pub struct Pattern {
+    pub mode: GameMode,
+    pub curve: Option<DifficultyCurve>,
+    difficulty: DifficultyStep,
     pub score: u32,
     pub multiplier: u16,
     pub scale: f32,
     time: f32,
     pub done: bool,
+    pub seed: u64,
+    pub taps: Vec<Tap>,
+    pub events: Vec<PatternEvent>,
+    pub expired: u32,
+    lives: u32,
+    // Accessibility assists, the round length is multiplied by timer_scale and miss_tolerance
+    // wrong clicks are forgiven each round
+    pub timer_scale: f32,
+    pub miss_tolerance: u32,
+    misses_left: u32,
+    start_time: f64,
+    end_time: f64,
     clicks: u64,
-    last_click_pos: (f32, f32),
+    // Hits since the last miss or expired tile, for the combo sound
+    streak: u64,
     last_time_clicked: f64,
+    paused_at: f64,
+    // Metronome ticks so far, and how far into the current beat the round is from 0 to 1
+    beats: u64,
+    beat_phase: f32,
+    // Seconds the last hit was off the beat, and when it was
+    beat_offset: Option<f32>,
+    beat_offset_at: f64,
     display_info: bool,
     display_grid: bool,
-    tiles: [bool; 16],
+    pub display_timer_bar: bool,
+    // Shapes on lit tiles so they can be told apart without their color
+    pub tile_cues: bool,
+    tiles: [Tile; CELLS],
     tiles_size: f32,
+    board_centre: (f32, f32),
+    board_angle: f32,
+    move_phase: f32,
     pub tiles_filled_color: Color,
     pub tiles_empty_color: Color,
+    pub tiles_decoy_color: Color,
+    pub tiles_bonus_color: Color,
+    pub tiles_time_color: Color,
+    pub tiles_multi_hit_color: Color,
     pub tiles_border_color: Color,
-    pub crosshair_color: Color,
-    pub score_color: Color,
-    rng: ThreadRng,
+    pub feedback: Feedback,
+    rng: StdRng,
 }
 
 impl Pattern {
@@ -38,27 +116,189 @@ impl Pattern {
     }
 
     pub fn setup(&mut self) {
+        let seed = thread_rng().gen();
+        self.setup_with_seed(seed);
+    }
+
+    pub fn setup_with_seed(&mut self, seed: u64) {
+        self.seed = seed;
+        self.rng = StdRng::seed_from_u64(seed);
+        self.taps = vec![];
+        self.events = vec![];
+        self.feedback.clear();
+        self.expired = 0;
+        self.lives = self.mode.lives;
+        self.misses_left = self.miss_tolerance;
+        self.start_time = get_time();
+        self.end_time = self.start_time;
         self.score = 0;
         self.multiplier = 1;
-        self.time = 30.0;
+        self.time = self.duration();
         self.clicks = 0;
-        self.last_click_pos = (0.0, 0.0);
-        self.last_time_clicked = 0.0;
+        self.streak = 0;
+        self.last_time_clicked = self.start_time;
+        self.beats = 0;
+        self.beat_phase = 0.0;
+        self.beat_offset = None;
         self.done = false;
-        self.tiles = [false; 16];
+        self.tiles = [Tile::empty(); CELLS];
+        self.board_angle = 0.0;
+        self.move_phase = 0.0;
+        self.update_difficulty();
+        self.fill_tiles(None);
+    }
+
+    // Length of the round before any time is added
+    pub fn duration(&self) -> f32 {
+        return self.mode.duration * self.timer_scale;
+    }
+
+    pub fn highscore_mode(&self) -> String {
+        return highscore_mode(&self.mode.name, self.timer_scale, self.miss_tolerance);
+    }
+
+    // Seconds since the round started, or the length of the round once it's done
+    pub fn elapsed(&self) -> f32 {
+        if self.done {
+            return (self.end_time - self.start_time) as f32;
+        }
+
+        return (get_time() - self.start_time) as f32;
+    }
+
+    pub fn pause(&mut self) {
+        self.paused_at = get_time();
+    }
+
+    // Moves the round's clock forward by the time spent paused, so it doesn't count
+    pub fn resume(&mut self) {
+        let paused_for = get_time() - self.paused_at;
+        self.start_time += paused_for;
+        self.last_time_clicked += paused_for;
+    }
+
+    // Seconds between metronome ticks, 0 without a metronome
+    fn beat_interval(&self) -> f32 {
+        if self.mode.bpm > 0.0 {
+            return 60.0 / self.mode.bpm;
+        }
+
+        return 0.0;
+    }
+
+    // Seconds from the nearest beat to `time` into the round, negative when early
+    fn beat_offset(&self, time: f32) -> f32 {
+        let interval = self.beat_interval();
+        let since = time % interval;
+        if since > interval / 2.0 {
+            return since - interval;
+        }
+
+        return since;
+    }
+
+    fn update_difficulty(&mut self) {
+        self.difficulty = match &self.curve {
+            Some(curve) => curve.at(self.score),
+            None => DifficultyStep::default(),
+        };
+    }
+
+    fn count_tiles(&self, matches: fn(TileKind) -> bool) -> usize {
+        return self.tiles.iter().filter(|tile| matches(tile.kind)).count();
+    }
+
+    // Lights random tiles until the difficulty's lit tile count is reached, never lighting `exclude`.
+    // Decoys don't count as lit tiles so they're spawned on top of that, up to MAX_DECOYS.
+    fn fill_tiles(&mut self, exclude: Option<usize>) {
+        let target = (self.difficulty.lit_tiles.round() as usize).clamp(1, 12);
+
+        while self.count_tiles(|kind| kind.is_target()) < target {
+            let free: Vec<usize> = (0..CELLS)
+                .filter(|&cell| self.tiles[cell].kind == TileKind::Empty && Some(cell) != exclude)
+                .collect();
+            if free.is_empty() {
+                return;
+            }
+
+            let new_cell = free[self.rng.gen_range(0..free.len())];
+            let mut kind = self.mode.tile_weights.pick(&mut self.rng);
+            if kind == TileKind::Decoy
+                && self.count_tiles(|kind| kind == TileKind::Decoy) >= MAX_DECOYS
+            {
+                kind = TileKind::Normal;
+            }
 
-        let mut generated_cells = 0;
-        while generated_cells < 3 {
-            let new_cell_pos = self.rng.gen_range(0..15);
-            let new_cell_val = self.tiles[new_cell_pos];
+            let lifetime = if kind == TileKind::Decoy {
+                DECOY_LIFETIME
+            } else {
+                self.tile_lifetime()
+            };
+            self.tiles[new_cell] = Tile::new(kind, lifetime);
+        }
+    }
+
+    fn tile_lifetime(&self) -> f32 {
+        return self.mode.tile_lifetime * self.difficulty.lifetime_scale;
+    }
+
+    // Counts down lit tiles and replaces the ones that ran out
+    fn expire_tiles(&mut self, dt: f32) {
+        for tile in 0..CELLS {
+            if self.tiles[tile].kind == TileKind::Empty || self.tiles[tile].lifetime <= 0.0 {
+                continue;
+            }
+
+            self.tiles[tile].life -= dt;
+            if self.tiles[tile].life > 0.0 {
+                continue;
+            }
 
-            if !new_cell_val {
-                self.tiles[new_cell_pos] = true;
-                generated_cells += 1;
+            let kind = self.tiles[tile].kind;
+            self.tiles[tile] = Tile::empty();
+            if kind == TileKind::Decoy {
+                self.fill_tiles(Some(tile));
+                continue;
             }
+
+            self.expired += 1;
+            self.streak = 0;
+            self.events.push(PatternEvent::Miss);
+            self.feedback.miss();
+            self.score = self.score.saturating_sub(self.mode.expire_penalty);
+            if self.mode.lives > 0 {
+                self.lives = self.lives.saturating_sub(1);
+                if self.lives == 0 {
+                    self.done = true;
+                }
+            }
+
+            self.update_difficulty();
+            self.fill_tiles(Some(tile));
         }
     }
 
+    // Position on the board, (0, 0) being its top left corner, to screen position
+    fn to_screen(&self, x: f32, y: f32) -> Vec2 {
+        let half = self.tiles_size / 2.0;
+        let (sin, cos) = self.board_angle.to_radians().sin_cos();
+        let (dx, dy) = (x - half, y - half);
+
+        return vec2(
+            self.board_centre.0 + dx * cos - dy * sin,
+            self.board_centre.1 + dx * sin + dy * cos,
+        );
+    }
+
+    // Screen position to position on the board, the inverse of `to_screen`
+    fn to_board(&self, pos: (f32, f32)) -> (f32, f32) {
+        let half = self.tiles_size / 2.0;
+        let (sin, cos) = self.board_angle.to_radians().sin_cos();
+        let (dx, dy) = (pos.0 - self.board_centre.0, pos.1 - self.board_centre.1);
+
+        return (dx * cos + dy * sin + half, -dx * sin + dy * cos + half);
+    }
+
     pub fn update(&mut self) {
         let current_time = get_time();
 
@@ -81,17 +321,31 @@ impl Pattern {
             self.scale -= SCALE_CHANGE;
         }
 
-        if self.scale > SCALE_MAX {
-            self.scale = SCALE_MAX;
-        } else if self.scale < SCALE_MIN {
-            self.scale = SCALE_MIN;
+        self.scale = self.scale.clamp(SCALE_MIN, SCALE_MAX);
+        self.tiles_size = px(250.0 * self.scale * self.difficulty.scale);
+
+        if !self.done {
+            // Board movement, drifts in a figure eight around the screen centre
+            let dt = get_frame_time();
+            self.move_phase += self.difficulty.move_speed * dt;
+            self.board_angle += self.difficulty.rotation_speed * dt;
         }
-        self.tiles_size = 250.0 * self.scale;
+        let radius = px(self.difficulty.move_radius);
+        let shake = self.feedback.shake();
+        self.board_centre = (
+            screen_width() / 2.0 + radius * self.move_phase.sin() + shake.x,
+            screen_height() / 2.0 + radius * (self.move_phase * 2.0).sin() / 2.0 + shake.y,
+        );
+        self.feedback.update();
 
         if !self.done {
             // Game time
             if self.time > 0.0 {
-                self.time = self.time - get_frame_time();
+                let before = self.time;
+                self.time -= get_frame_time();
+                if self.time > 0.0 && self.time <= COUNTDOWN_SECONDS && self.time.ceil() < before.ceil() {
+                    self.events.push(PatternEvent::Countdown);
+                }
 
                 let dt_click = current_time - self.last_time_clicked;
                 self.multiplier = (1.0 + (dt_click * (self.clicks as f64 / 4.5))).floor() as u16;
@@ -100,243 +354,437 @@ impl Pattern {
                 self.done = true;
             }
 
-            // Tiles
-            let xy = (
-                (screen_width() / 2.0) - self.tiles_size / 2.0,
-                (screen_height() / 2.0) - self.tiles_size / 2.0,
-            );
-            let cell = self.tiles_size / 4.0;
+            self.expire_tiles(get_frame_time());
+
+            // Metronome, the first tick is right at the start
+            let interval = self.beat_interval();
+            if interval > 0.0 {
+                let elapsed = (current_time - self.start_time) as f32;
+                let beat = (elapsed / interval) as u64 + 1;
+                if beat > self.beats {
+                    self.beats = beat;
+                    self.events.push(PatternEvent::Beat);
+                }
+                self.beat_phase = (elapsed % interval) / interval;
+            }
 
+            // Tiles
             if is_mouse_button_pressed(MouseButton::Left) {
-                let mouse_pos = mouse_position();
-                if (mouse_pos.0 > xy.0 && mouse_pos.1 > xy.1)
-                    && (mouse_pos.0 < xy.0 + cell * 4.0 && mouse_pos.1 < xy.1 + cell * 4.0)
+                let board_pos = self.to_board(mouse_position());
+                if (board_pos.0 > 0.0 && board_pos.1 > 0.0)
+                    && (board_pos.0 < self.tiles_size && board_pos.1 < self.tiles_size)
                 {
-                    let x = ((mouse_pos.0 - xy.0) / self.tiles_size * 4.0).floor();
-                    let y = ((mouse_pos.1 - xy.1) / self.tiles_size * 4.0).floor();
+                    let exact_x = board_pos.0 / self.tiles_size * 4.0;
+                    let exact_y = board_pos.1 / self.tiles_size * 4.0;
+                    let x = exact_x.floor();
+                    let y = exact_y.floor();
                     let cell_pos = (x + (y * 4.0).floor()) as usize;
 
-                    let cell_val = self.tiles[cell_pos];
+                    let kind = self.tiles[cell_pos].kind;
+                    let cell_val = kind.is_target();
+                    let time = (current_time - self.start_time) as f32;
+                    self.taps.push(Tap {
+                        time,
+                        reaction: (current_time - self.last_time_clicked) as f32,
+                        cell: cell_pos as u8,
+                        offset_x: exact_x - x - 0.5,
+                        offset_y: exact_y - y - 0.5,
+                        hit: cell_val,
+                    });
+
                     if cell_val {
-                        let mut new_cell_found = false;
-                        while !new_cell_found {
-                            let new_cell = self.rng.gen_range(0..15);
-                            let new_cell_val = self.tiles[new_cell];
-
-                            if !new_cell_val {
-                                self.tiles[new_cell] = !new_cell_val;
-                                new_cell_found = true;
-                            }
+                        // On the metronome only the timing counts, not the speed
+                        let mut points = if interval > 0.0 {
+                            let offset = self.beat_offset(time);
+                            self.beat_offset = Some(offset);
+                            self.beat_offset_at = current_time;
+                            beat_points(offset)
+                        } else {
+                            self.multiplier as u32
+                        };
+                        if kind == TileKind::Bonus {
+                            points *= BONUS_MULTIPLIER;
+                        }
+                        if kind == TileKind::Time {
+                            self.time += TIME_EXTENSION;
                         }
 
-                        self.tiles[cell_pos] = !self.tiles[cell_pos];
-                        self.score += self.multiplier as u32;
+                        self.score += points;
+                        let multiplier = if interval > 0.0 { 1 } else { self.multiplier };
+                        self.feedback.hit(cell_pos, points, multiplier);
+                        self.time += self.mode.hit_bonus(self.clicks);
                         self.last_time_clicked = current_time;
                         self.clicks += 1;
+                        self.streak += 1;
+                        if self.streak % COMBO_TIER == 0 {
+                            self.events.push(PatternEvent::ComboTier);
+                        } else {
+                            self.events.push(PatternEvent::Hit);
+                        }
+
+                        self.tiles[cell_pos].hits_left -= 1;
+                        if self.tiles[cell_pos].hits_left == 0 {
+                            self.tiles[cell_pos] = Tile::empty();
+                            self.update_difficulty();
+                            self.fill_tiles(Some(cell_pos));
+                        }
                     } else {
-                        self.last_click_pos = mouse_pos;
-                        self.done = true;
+                        self.events.push(PatternEvent::Miss);
+                        self.feedback.miss();
+                        self.streak = 0;
+                        if self.misses_left > 0 {
+                            self.misses_left -= 1;
+                        } else {
+                            self.done = true;
+                        }
                     }
                 }
             }
+
+            if self.done {
+                self.end_time = current_time;
+                self.events.push(PatternEvent::RoundEnd);
+            }
         }
     }
 
-    pub fn render(&mut self, font: Font) {
-        let xy = (
-            (screen_width() / 2.0) - self.tiles_size / 2.0,
-            (screen_height() / 2.0) - self.tiles_size / 2.0,
-        );
+    pub fn render(&mut self) {
         let cell = self.tiles_size / 4.0;
 
         // display_info information
         if self.display_info {
-            draw_text(&*format!("Score: {}", self.score), 50.0, 70.0, 32.0, WHITE);
-            draw_text(
-                &*format!("Multiplier: {}", self.multiplier),
-                50.0,
-                100.0,
-                32.0,
-                WHITE,
-            );
-            draw_text(&*format!("Scale: {}", self.scale), 50.0, 130.0, 32.0, WHITE);
-            draw_text(
-                &*format!("Time: {}", self.time.floor()),
-                50.0,
-                160.0,
-                32.0,
-                WHITE,
-            );
+            let mut lines = vec![
+                tr_with("round.score", &[("score", self.score.to_string())]),
+                tr_with("round.multiplier", &[("multiplier", self.multiplier.to_string())]),
+                tr_with("round.scale", &[("scale", self.scale.to_string())]),
+                tr_with("round.time", &[("time", self.time.floor().to_string())]),
+                tr_with("round.mode", &[("mode", self.mode.name.clone())]),
+            ];
+            if self.mode.lives > 0 {
+                lines.push(tr_with("round.lives", &[("lives", self.lives.to_string())]));
+            }
+            if self.mode.bpm > 0.0 {
+                lines.push(tr_with("round.bpm", &[("bpm", self.mode.bpm.to_string())]));
+            }
+            if self.miss_tolerance > 0 {
+                lines.push(tr_with("round.misses_left", &[("misses", self.misses_left.to_string())]));
+            }
+            for (i, line) in lines.iter().enumerate() {
+                let position = at(Anchor::TopLeft, 50.0, 70.0 + i as f32 * 30.0 * text_scale());
+                draw_text_ex(line, position.x, position.y, text::params(TextStyle::Hud));
+            }
         }
 
         // Tile background. IE "outer grid lines"
         if self.display_grid {
-            draw_rectangle(
-                xy.0 - 2.0,
-                xy.1 - 2.0,
-                self.tiles_size + 4.0,
-                self.tiles_size + 4.0,
+            self.draw_board_rect(
+                -px(2.0),
+                -px(2.0),
+                self.tiles_size + px(4.0),
+                self.tiles_size + px(4.0),
                 self.tiles_border_color,
             );
         }
 
-        // Tiles and grid
-        for tile in 0..16 {
-            let filled: bool = self.tiles[tile];
+        if self.display_timer_bar {
+            self.render_timer_bar();
+        }
 
-            let color: Color;
-            if filled {
-                color = self.tiles_filled_color;
-            } else {
-                color = self.tiles_empty_color;
-            }
+        if self.mode.bpm > 0.0 {
+            self.render_beat();
+        }
 
-            // Tiles
-            match tile {
-                0 => draw_rectangle(xy.0, xy.1, cell, cell, color),
-                1 => draw_rectangle(xy.0 + cell, xy.1, cell, cell, color),
-                2 => draw_rectangle(xy.0 + cell * 2.0, xy.1, cell, cell, color),
-                3 => draw_rectangle(xy.0 + cell * 3.0, xy.1, cell, cell, color),
-
-                4 => draw_rectangle(xy.0, xy.1 + cell, cell, cell, color),
-                5 => draw_rectangle(xy.0 + cell, xy.1 + cell, cell, cell, color),
-                6 => draw_rectangle(xy.0 + cell * 2.0, xy.1 + cell, cell, cell, color),
-                7 => draw_rectangle(xy.0 + cell * 3.0, xy.1 + cell, cell, cell, color),
-
-                8 => draw_rectangle(xy.0, xy.1 + (cell * 2.0), cell, cell, color),
-                9 => draw_rectangle(xy.0 + cell, xy.1 + (cell * 2.0), cell, cell, color),
-                10 => draw_rectangle(xy.0 + cell * 2.0, xy.1 + (cell * 2.0), cell, cell, color),
-                11 => draw_rectangle(xy.0 + cell * 3.0, xy.1 + (cell * 2.0), cell, cell, color),
-
-                12 => draw_rectangle(xy.0, xy.1 + (cell * 3.0), cell, cell, color),
-                13 => draw_rectangle(xy.0 + cell, xy.1 + (cell * 3.0), cell, cell, color),
-                14 => draw_rectangle(xy.0 + cell * 2.0, xy.1 + (cell * 3.0), cell, cell, color),
-                15 => draw_rectangle(xy.0 + cell * 3.0, xy.1 + (cell * 3.0), cell, cell, color),
-                _ => {}
+        // Tiles
+        for index in 0..CELLS {
+            let x = (index % 4) as f32 * cell;
+            let y = (index / 4) as f32 * cell;
+            self.draw_board_rect(x, y, cell, cell, self.tiles_empty_color);
+
+            let tile = self.tiles[index];
+            if tile.kind != TileKind::Empty {
+                // Expiring tiles shrink and fade towards their centre
+                let mut color = self.tile_color(tile.kind);
+                let mut size = cell;
+                if tile.lifetime > 0.0 {
+                    let left = (tile.life / tile.lifetime).clamp(0.0, 1.0);
+                    size = cell * (0.3 + 0.7 * left);
+                    color.a *= 0.4 + 0.6 * left;
+                }
+
+                let inset = (cell - size) / 2.0;
+                self.draw_board_rect(x + inset, y + inset, size, size, color);
+
+                if self.tile_cues {
+                    self.render_cue(tile.kind, x + inset, y + inset, size);
+                }
+
+                if tile.kind == TileKind::MultiHit {
+                    let centre = self.to_screen(x + cell / 2.0, y + cell / 2.0);
+                    let text = tile.hits_left.to_string();
+                    let dimensions = text::measure(&text, TextStyle::Hud);
+                    draw_text_ex(
+                        &text,
+                        centre.x - dimensions.width / 2.0,
+                        centre.y + dimensions.height / 2.0,
+                        TextParams {
+                            color: self.tiles_empty_color,
+                            ..text::params(TextStyle::Hud)
+                        },
+                    );
+                }
             }
 
-            if self.display_grid {
-                // Grid vertical
-                draw_line(
-                    xy.0 + cell,
-                    xy.1,
-                    xy.0 + cell,
-                    xy.1 + cell * 4.0,
-                    2.0,
-                    self.tiles_border_color,
-                );
-                draw_line(
-                    xy.0 + cell * 2.0,
-                    xy.1,
-                    xy.0 + cell * 2.0,
-                    xy.1 + cell * 4.0,
-                    2.0,
-                    self.tiles_border_color,
-                );
-                draw_line(
-                    xy.0 + cell * 3.0,
-                    xy.1,
-                    xy.0 + cell * 3.0,
-                    xy.1 + cell * 4.0,
-                    2.0,
-                    self.tiles_border_color,
-                );
+            // Hit cells flash and pop out, easing back
+            if let Some(pop) = self.feedback.pop(index) {
+                let size = cell * (1.0 + POP_SCALE * pop);
+                let inset = (cell - size) / 2.0;
+                let mut color = self.tiles_border_color;
+                color.a *= 0.6 * pop;
+                self.draw_board_rect(x + inset, y + inset, size, size, color);
+            }
+        }
 
-                // Grid horizontal
-                draw_line(
-                    xy.0,
-                    xy.1 + cell,
-                    xy.0 + cell * 4.0,
-                    xy.1 + cell,
-                    2.0,
-                    self.tiles_border_color,
-                );
-                draw_line(
-                    xy.0,
-                    xy.1 + cell * 2.0,
-                    xy.0 + cell * 4.0,
-                    xy.1 + cell * 2.0,
-                    2.0,
-                    self.tiles_border_color,
-                );
-                draw_line(
-                    xy.0,
-                    xy.1 + cell * 3.0,
-                    xy.0 + cell * 4.0,
-                    xy.1 + cell * 3.0,
-                    2.0,
-                    self.tiles_border_color,
-                );
+        // Grid
+        if self.display_grid {
+            for line in 1..4 {
+                let offset = cell * line as f32;
+                self.draw_board_line(offset, 0.0, offset, self.tiles_size);
+                self.draw_board_line(0.0, offset, self.tiles_size, offset);
             }
         }
 
-        if self.done {
-            let score_text = &*format!("SCORE: {}!!!", self.score);
-            let score_text_dim = measure_text(score_text, Some(font), 64, 1.0);
-            let color = self.score_color;
+        // Score popups float up from the cells that were hit
+        for (text, cell_index, rise, alpha) in self.feedback.popups() {
+            let centre = self.to_screen(
+                (cell_index % 4) as f32 * cell + cell / 2.0,
+                (cell_index / 4) as f32 * cell + cell / 2.0,
+            );
+            let mut color = self.tiles_border_color;
+            color.a *= alpha;
+            let dimensions = text::measure(text, TextStyle::Hud);
             draw_text_ex(
-                score_text,
-                screen_width() / 2.0 - score_text_dim.width / 2.0,
-                (screen_height() / 2.0) - (self.tiles_size / 2.0) - 50.0,
+                text,
+                centre.x - dimensions.width / 2.0,
+                centre.y - rise,
                 TextParams {
-                    font,
-                    font_size: 64,
                     color,
-                    ..Default::default()
+                    ..text::params(TextStyle::Hud)
                 },
             );
+        }
+
+        self.feedback.render_flash();
+    }
+
+    // A shape for each kind of lit tile in the empty tile color: a frame for normal and multi hit
+    // tiles, a cross for decoys, a diamond for bonus tiles and a circle for time tiles
+    fn render_cue(&self, kind: TileKind, x: f32, y: f32, size: f32) {
+        let color = self.tiles_empty_color;
+        let width = px(3.0);
+        let (inset, inner) = (size * 0.2, size * 0.6);
+        let (left, top, right, bottom) = (x + inset, y + inset, x + inset + inner, y + inset + inner);
+        let (centre_x, centre_y) = (x + size / 2.0, y + size / 2.0);
+        let line = |x1: f32, y1: f32, x2: f32, y2: f32| {
+            let start = self.to_screen(x1, y1);
+            let end = self.to_screen(x2, y2);
+            draw_line(start.x, start.y, end.x, end.y, width, color);
+        };
+
+        match kind {
+            TileKind::Empty => {}
+            TileKind::Normal | TileKind::MultiHit => {
+                line(left, top, right, top);
+                line(right, top, right, bottom);
+                line(right, bottom, left, bottom);
+                line(left, bottom, left, top);
+            }
+            TileKind::Decoy => {
+                line(left, top, right, bottom);
+                line(right, top, left, bottom);
+            }
+            TileKind::Bonus => {
+                line(centre_x, top, right, centre_y);
+                line(right, centre_y, centre_x, bottom);
+                line(centre_x, bottom, left, centre_y);
+                line(left, centre_y, centre_x, top);
+            }
+            TileKind::Time => {
+                let centre = self.to_screen(centre_x, centre_y);
+                draw_circle_lines(centre.x, centre.y, inner / 2.0, width, color);
+                line(centre_x, centre_y, centre_x, top + inner * 0.2);
+            }
+        }
+    }
+
+    fn tile_color(&self, kind: TileKind) -> Color {
+        match kind {
+            TileKind::Empty => return self.tiles_empty_color,
+            TileKind::Normal => return self.tiles_filled_color,
+            TileKind::Decoy => return self.tiles_decoy_color,
+            TileKind::Bonus => return self.tiles_bonus_color,
+            TileKind::Time => return self.tiles_time_color,
+            TileKind::MultiHit => return self.tiles_multi_hit_color,
+        }
+    }
+
+    fn draw_board_rect(&self, x: f32, y: f32, w: f32, h: f32, color: Color) {
+        let top_left = self.to_screen(x, y);
+        let top_right = self.to_screen(x + w, y);
+        let bottom_right = self.to_screen(x + w, y + h);
+        let bottom_left = self.to_screen(x, y + h);
+
+        draw_triangle(top_left, top_right, bottom_right, color);
+        draw_triangle(top_left, bottom_right, bottom_left, color);
+    }
+
+    fn draw_board_line(&self, x1: f32, y1: f32, x2: f32, y2: f32) {
+        let start = self.to_screen(x1, y1);
+        let end = self.to_screen(x2, y2);
+        draw_line(start.x, start.y, end.x, end.y, px(2.0), self.tiles_border_color);
+    }
+
+    // The board's edge flashes on every beat, and the timing of the last hit is shown above it
+    fn render_beat(&self) {
+        let flash = (1.0 - self.beat_phase * 4.0).max(0.0);
+        if flash > 0.0 && !self.done {
+            let mut color = self.tiles_border_color;
+            color.a *= flash;
+            let offset = px(6.0);
+            self.draw_board_rect(
+                -offset,
+                -offset,
+                self.tiles_size + offset * 2.0,
+                self.tiles_size + offset * 2.0,
+                color,
+            );
+        }
 
-            // Last clicked position
-            draw_circle(
-                self.last_click_pos.0,
-                self.last_click_pos.1,
-                4.0,
-                self.crosshair_color,
+        if let Some(offset) = self.beat_offset {
+            if get_time() - self.beat_offset_at < BEAT_LABEL_TIME {
+                let text = format!("{} {:+.0}ms", beat_label(offset), offset * 1000.0);
+                let dimensions = text::measure(&text, TextStyle::Hud);
+                draw_text_ex(
+                    &text,
+                    self.board_centre.0 - dimensions.width / 2.0,
+                    self.board_centre.1 - self.tiles_size * 0.75 - px(20.0),
+                    text::params(TextStyle::Hud),
+                );
+            }
+        }
+    }
+
+    // Remaining time as a bar running clockwise around the board from the top left corner
+    fn render_timer_bar(&self) {
+        let offset = px(10.0);
+        let side = self.tiles_size + offset * 2.0;
+        let left = -offset;
+        let top = -offset;
+        let fraction = (self.time / self.duration()).clamp(0.0, 1.0);
+
+        let corners = [
+            (left, top),
+            (left + side, top),
+            (left + side, top + side),
+            (left, top + side),
+            (left, top),
+        ];
+        let mut remaining = fraction * side * 4.0;
+        for edge in corners.windows(2) {
+            if remaining <= 0.0 {
+                break;
+            }
+
+            let length = remaining.min(side);
+            let (start, end) = (edge[0], edge[1]);
+            let screen_start = self.to_screen(start.0, start.1);
+            let screen_end = self.to_screen(
+                start.0 + (end.0 - start.0) * length / side,
+                start.1 + (end.1 - start.1) * length / side,
+            );
+            draw_line(
+                screen_start.x,
+                screen_start.y,
+                screen_end.x,
+                screen_end.y,
+                px(4.0),
+                self.tiles_border_color,
             );
+            remaining -= length;
         }
+    }
+}
 
-        // Cursor/Pointer
-        let mouse_pos = mouse_position();
-        draw_line(
-            mouse_pos.0,
-            mouse_pos.1 - 5.0,
-            mouse_pos.0,
-            mouse_pos.1 + 5.0,
-            2.0,
-            self.crosshair_color,
-        );
-        draw_line(
-            mouse_pos.0 - 5.0,
-            mouse_pos.1,
-            mouse_pos.0 + 5.0,
-            mouse_pos.1,
-            2.0,
-            self.crosshair_color,
-        );
+// Points for a hit `offset` seconds off the beat
+fn beat_points(offset: f32) -> u32 {
+    let offset = offset.abs();
+    if offset <= BEAT_PERFECT {
+        return 3;
+    } else if offset <= BEAT_GOOD {
+        return 2;
     }
+
+    return 1;
+}
+
+fn beat_label(offset: f32) -> String {
+    if offset.abs() <= BEAT_PERFECT {
+        return tr("round.perfect");
+    } else if offset.abs() <= BEAT_GOOD {
+        return tr("round.good");
+    } else if offset < 0.0 {
+        return tr("round.early");
+    }
+
+    return tr("round.late");
 }
 
 impl Default for Pattern {
     fn default() -> Pattern {
         return Pattern {
+            mode: GameMode::default(),
+            curve: None,
+            difficulty: DifficultyStep::default(),
             score: 0,
             multiplier: 1,
             scale: 1.0,
-            time: 30.0,
+            time: 0.0,
             done: false,
+            seed: 0,
+            taps: vec![],
+            events: vec![],
+            expired: 0,
+            lives: 0,
+            timer_scale: 1.0,
+            miss_tolerance: 0,
+            misses_left: 0,
+            start_time: 0.0,
+            end_time: 0.0,
             clicks: 0,
-            last_click_pos: (0.0, 0.0),
+            streak: 0,
             last_time_clicked: 0.0,
+            paused_at: 0.0,
+            beats: 0,
+            beat_phase: 0.0,
+            beat_offset: None,
+            beat_offset_at: 0.0,
             display_info: true,
             display_grid: true,
-            tiles: [false; 16],
+            display_timer_bar: true,
+            tile_cues: false,
+            tiles: [Tile::empty(); CELLS],
             tiles_size: 250.0,
+            board_centre: (0.0, 0.0),
+            board_angle: 0.0,
+            move_phase: 0.0,
             tiles_filled_color: WHITE,
             tiles_empty_color: WHITE,
+            tiles_decoy_color: WHITE,
+            tiles_bonus_color: WHITE,
+            tiles_time_color: WHITE,
+            tiles_multi_hit_color: WHITE,
             tiles_border_color: WHITE,
-            crosshair_color: WHITE,
-            score_color: WHITE,
-            rng: thread_rng(),
+            feedback: Feedback::new(),
+            rng: StdRng::seed_from_u64(0),
         };
     }
 }
@@ -0,0 +1,199 @@
+use std::{
+    fs,
+    path::PathBuf,
+    sync::atomic::{AtomicU32, Ordering},
+};
+
+use super::achievements::Achievements;
+use super::highscore::{Highscore, HighscoreTable};
+use super::history::{History, RoundRecord};
+use super::locale::{tr, tr_with};
+use super::paths::paths;
+use super::saves::load_profiles;
+
+// The profile new saves and saves from before profiles start with
+pub const DEFAULT_PROFILE: &str = "Player";
+pub const MAX_NAME_LENGTH: usize = 16;
+
+// Id of the profile being played, picks the settings file
+static ACTIVE_ID: AtomicU32 = AtomicU32::new(0);
+
+// One player's highscores, round history and achievements, their settings are in a file of their own
+#[derive(Savefile, Clone)]
+pub struct Profile {
+    // Stays the same when the profile is renamed
+    pub id: u32,
+    pub name: String,
+    pub highscores: Vec<HighscoreTable>,
+    pub history: Vec<RoundRecord>,
+    #[savefile_versions = "4.."]
+    pub achievements: Vec<String>,
+}
+
+impl Profile {
+    pub fn new(id: u32, name: &str) -> Self {
+        return Self {
+            id,
+            name: name.to_owned(),
+            highscores: vec![],
+            history: vec![],
+            achievements: vec![],
+        };
+    }
+}
+
+// Every profile on this machine. The active one's highscores, history and achievements are played with from
+// the context, what's kept here for it is only brought up to date on save and when switching.
+#[derive(Clone)]
+pub struct Profiles {
+    pub list: Vec<Profile>,
+    pub active: usize,
+}
+
+impl Profiles {
+    pub fn new() -> Self {
+        return Self {
+            ..Default::default()
+        };
+    }
+
+    pub fn active(&self) -> &Profile {
+        return &self.list[self.active];
+    }
+
+    pub fn active_mut(&mut self) -> &mut Profile {
+        return &mut self.list[self.active];
+    }
+
+    // Makes the active profile's settings the ones read and written
+    pub fn activate(&self) {
+        ACTIVE_ID.store(self.active().id, Ordering::Relaxed);
+    }
+
+    // Puts the active profile's highscores, history and achievements into the ones being played with
+    pub fn load_active(&self, highscore: &mut Highscore, history: &mut History, achievements: &mut Achievements) {
+        let profile = self.active();
+        highscore.tables = profile.highscores.clone();
+        history.rounds = profile.history.clone();
+        achievements.unlocked = profile.achievements.clone();
+        self.activate();
+    }
+
+    // Keeps what has been played in the active profile
+    pub fn store(&mut self, highscore: &Highscore, history: &History, achievements: &Achievements) {
+        let profile = self.active_mut();
+        profile.highscores = highscore.tables.clone();
+        profile.history = history.rounds.clone();
+        profile.achievements = achievements.unlocked.clone();
+    }
+
+    pub fn switch(
+        &mut self,
+        i: usize,
+        highscore: &mut Highscore,
+        history: &mut History,
+        achievements: &mut Achievements,
+    ) {
+        self.store(highscore, history, achievements);
+        self.active = i;
+        self.load_active(highscore, history, achievements);
+    }
+
+    // Adds a profile starting out with a copy of the active profile's settings
+    pub fn create(&mut self, name: &str) -> Result<usize, String> {
+        let name = self.check_name(name, None)?;
+        let id = self.list.iter().map(|profile| profile.id).max().unwrap_or(0) + 1;
+
+        let settings = config_path();
+        if settings.exists() {
+            fs::copy(&settings, config_path_for(id)).map_err(|err| err.to_string())?;
+        }
+        self.list.push(Profile::new(id, &name));
+
+        return Ok(self.list.len() - 1);
+    }
+
+    pub fn rename(&mut self, i: usize, name: &str) -> Result<(), String> {
+        self.list[i].name = self.check_name(name, Some(i))?;
+        return Ok(());
+    }
+
+    // Deletes the active profile with its settings and switches to the first one left.
+    // The first profile's settings file is the one the game started out with, so it's kept.
+    pub fn delete_active(
+        &mut self,
+        highscore: &mut Highscore,
+        history: &mut History,
+        achievements: &mut Achievements,
+    ) -> Result<(), String> {
+        if self.list.len() < 2 {
+            return Err(tr("profiles.last"));
+        }
+
+        let profile = self.list.remove(self.active);
+        if profile.id != 0 {
+            let settings = config_path_for(profile.id);
+            if settings.exists() {
+                fs::remove_file(settings).map_err(|err| err.to_string())?;
+            }
+        }
+        self.active = 0;
+        self.load_active(highscore, history, achievements);
+
+        return Ok(());
+    }
+
+    // The name trimmed, if it isn't empty, too long or taken by another profile than `except`
+    fn check_name(&self, name: &str, except: Option<usize>) -> Result<String, String> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(tr("profiles.empty_name"));
+        }
+        if name.chars().count() > MAX_NAME_LENGTH {
+            return Err(tr_with("profiles.long_name", &[("max", MAX_NAME_LENGTH.to_string())]));
+        }
+        let taken = self
+            .list
+            .iter()
+            .enumerate()
+            .any(|(i, profile)| Some(i) != except && profile.name == name);
+        if taken {
+            return Err(tr_with("profiles.name_taken", &[("name", name.to_owned())]));
+        }
+
+        return Ok(name.to_owned());
+    }
+}
+
+impl Default for Profiles {
+    fn default() -> Profiles {
+        return Profiles {
+            list: vec![Profile::new(0, DEFAULT_PROFILE)],
+            active: 0,
+        };
+    }
+}
+
+// Settings file of the active profile
+pub fn config_path() -> PathBuf {
+    return config_path_for(ACTIVE_ID.load(Ordering::Relaxed));
+}
+
+// The first profile uses the settings file the game is given, the others one next to it
+// named after their id, like "settings-1.toml"
+fn config_path_for(id: u32) -> PathBuf {
+    let config = &paths().config;
+    if id == 0 {
+        return config.clone();
+    }
+
+    let stem = config.file_stem().map_or("settings".into(), |stem| stem.to_string_lossy());
+    let extension = config.extension().map_or("toml".into(), |extension| extension.to_string_lossy());
+    return config.with_file_name(format!("{}-{}.{}", stem, id, extension));
+}
+
+// The settings of the profile played last are used until the save is loaded, for the window
+// and the commands that don't open the game
+pub fn select_saved() {
+    load_profiles().activate();
+}
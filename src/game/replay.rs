@@ -0,0 +1,50 @@
+use std::fs::create_dir_all;
+
+extern crate savefile;
+use savefile::prelude::*;
+
+use super::paths::{path_str, paths};
+use super::pattern::Tap;
+
+// Next to the save file
+const REPLAY_DIR: &str = "replays";
+
+#[derive(Savefile)]
+pub struct Replay {
+    // When the round was played, in seconds since the Unix epoch
+    pub timestamp: u64,
+    pub seed: u64,
+    pub score: u32,
+    pub taps: Vec<Tap>,
+}
+
+impl Replay {
+    pub fn new() -> Self {
+        return Self {
+            timestamp: 0,
+            seed: 0,
+            score: 0,
+            taps: vec![],
+        };
+    }
+
+    pub fn new_from_data(timestamp: u64, seed: u64, score: u32, taps: Vec<Tap>) -> Self {
+        return Self {
+            timestamp,
+            seed,
+            score,
+            taps,
+        };
+    }
+
+    // Saves to "replays/replay_<timestamp>_<seed>.bin" in the save file's directory and returns the path written to
+    pub fn save_to_file(&self) -> Result<String, SavefileError> {
+        let dir = paths().save.with_file_name(REPLAY_DIR);
+        create_dir_all(&dir)?;
+
+        let path = path_str(&dir.join(format!("replay_{}_{}.bin", self.timestamp, self.seed)));
+        save_file(&path, 0, self)?;
+
+        return Ok(path);
+    }
+}
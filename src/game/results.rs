@@ -0,0 +1,207 @@
+use macroquad::prelude::*;
+
+use super::heatmap::Heatmap;
+use super::history::RoundRecord;
+use super::layout::{at, draw_text_centred, px, Anchor};
+use super::locale::{tr, tr_with};
+use super::menu::{Menu, MenuEvent, MenuItem};
+use super::replay::Replay;
+use super::text::{self, TextStyle};
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum ResultsSelect {
+    Retry,
+    SaveReplay,
+    Menu,
+}
+
+pub struct Results {
+    pub score_color: Color,
+    mode: String,
+    score: u32,
+    rank: Option<usize>,
+    new_record: bool,
+    taps: usize,
+    expired: Option<u32>,
+    accuracy: f32,
+    taps_per_second: f32,
+    best_reaction: f32,
+    mean_reaction: f32,
+    median_reaction: f32,
+    seed: u64,
+    heatmap: Heatmap,
+    replay: Replay,
+    replay_status: String,
+    menu: Menu<ResultsSelect>,
+}
+
+impl Results {
+    pub fn new() -> Self {
+        return Self {
+            ..Default::default()
+        };
+    }
+
+    pub fn setup(&mut self, round: &RoundRecord, rank: Option<usize>, new_record: bool) {
+        let hits: Vec<f32> = round
+            .taps
+            .iter()
+            .filter(|tap| tap.hit)
+            .map(|tap| tap.reaction)
+            .collect();
+        let round_length = round.length;
+
+        self.menu.reset();
+        self.mode = round.mode.clone();
+        self.score = round.score;
+        self.rank = rank;
+        self.new_record = new_record;
+        self.taps = round.taps.len();
+        self.expired = if round.tile_lifetime > 0.0 {
+            Some(round.expired)
+        } else {
+            None
+        };
+        self.accuracy = if self.taps > 0 {
+            hits.len() as f32 / self.taps as f32 * 100.0
+        } else {
+            0.0
+        };
+        self.taps_per_second = if round_length > 0.0 {
+            hits.len() as f32 / round_length
+        } else {
+            0.0
+        };
+        self.best_reaction = hits.iter().cloned().fold(f32::NAN, f32::min);
+        self.mean_reaction = hits.iter().sum::<f32>() / hits.len() as f32;
+        self.median_reaction = median(&hits);
+        self.seed = round.seed;
+        self.heatmap = Heatmap::from_taps(round.taps.iter());
+        self.replay = Replay::new_from_data(round.timestamp, round.seed, round.score, round.taps.clone());
+        self.replay_status = String::new();
+    }
+
+    pub fn save_replay(&mut self) {
+        self.replay_status = match self.replay.save_to_file() {
+            Ok(path) => tr_with("results.replay_saved", &[("path", path)]),
+            Err(err) => tr_with("results.replay_failed", &[("error", err.to_string())]),
+        };
+    }
+
+    pub fn update(&mut self) -> Option<MenuEvent<ResultsSelect>> {
+        return self.menu.update();
+    }
+
+    pub fn render(&mut self) {
+        let title = &tr("results.title");
+        let title_position = at(Anchor::Centre, 0.0, -250.0);
+        draw_text_centred(title, title_position.x, title_position.y, text::params(TextStyle::Title));
+
+        let score_key = if self.new_record { "results.new_record" } else { "results.score" };
+        let score_text = tr_with(score_key, &[("score", self.score.to_string())]);
+        let position = at(Anchor::Left, 250.0, -175.0);
+        draw_text_ex(
+            &score_text,
+            position.x,
+            position.y,
+            TextParams {
+                color: self.score_color,
+                ..text::params(TextStyle::Heading)
+            },
+        );
+
+        let rank = match self.rank {
+            Some(rank) => format!("#{}", rank),
+            None => "-".to_owned(),
+        };
+        let mut lines = vec![
+            tr_with("results.mode", &[("mode", self.mode.clone())]),
+            tr_with("results.rank", &[("rank", rank)]),
+            tr_with("results.taps", &[("taps", self.taps.to_string())]),
+            tr_with("results.accuracy", &[("accuracy", format!("{:.1}", self.accuracy))]),
+            tr_with("results.taps_per_second", &[("taps", format!("{:.2}", self.taps_per_second))]),
+            tr_with("results.best_reaction", &[("reaction", format_reaction(self.best_reaction))]),
+            tr_with("results.mean_reaction", &[("reaction", format_reaction(self.mean_reaction))]),
+            tr_with("results.median_reaction", &[("reaction", format_reaction(self.median_reaction))]),
+            tr_with("results.seed", &[("seed", self.seed.to_string())]),
+        ];
+        if let Some(expired) = self.expired {
+            lines.insert(3, tr_with("results.expired", &[("expired", expired.to_string())]));
+        }
+        for (i, line) in lines.iter().enumerate() {
+            let position = at(Anchor::Left, 250.0, -120.0 + i as f32 * 30.0);
+            draw_text_ex(line, position.x, position.y, text::params(TextStyle::Body));
+        }
+
+        // Where the taps landed, next to the numbers
+        let heatmap_size = 300.0;
+        let heatmap = at(Anchor::Right, -heatmap_size - 250.0, -175.0);
+        self.heatmap.render(heatmap.x, heatmap.y, px(heatmap_size));
+
+        self.menu.render();
+
+        let position = at(Anchor::Left, 250.0, 325.0);
+        draw_text_ex(
+            &self.replay_status,
+            position.x,
+            position.y,
+            text::params(TextStyle::Small),
+        );
+    }
+}
+
+impl Default for Results {
+    fn default() -> Results {
+        let mut menu = Menu::new(
+            "",
+            vec![
+                MenuItem::new(&tr("results.retry"), ResultsSelect::Retry),
+                MenuItem::new(&tr("results.save_replay"), ResultsSelect::SaveReplay),
+                MenuItem::new(&tr("results.menu"), ResultsSelect::Menu),
+            ],
+        );
+        menu.top = 175.0;
+
+        return Results {
+            score_color: WHITE,
+            mode: String::new(),
+            score: 0,
+            rank: None,
+            new_record: false,
+            taps: 0,
+            expired: None,
+            accuracy: 0.0,
+            taps_per_second: 0.0,
+            best_reaction: f32::NAN,
+            mean_reaction: f32::NAN,
+            median_reaction: f32::NAN,
+            seed: 0,
+            heatmap: Heatmap::new(),
+            replay: Replay::new(),
+            replay_status: String::new(),
+            menu,
+        };
+    }
+}
+
+fn median(values: &[f32]) -> f32 {
+    if values.is_empty() {
+        return f32::NAN;
+    }
+
+    let mut sorted = values.to_vec();
+    sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+    // Both indices are the same element for odd lengths
+    let len = sorted.len();
+    return (sorted[(len - 1) / 2] + sorted[len / 2]) / 2.0;
+}
+
+// Reaction times are stored in seconds but read better as milliseconds
+fn format_reaction(seconds: f32) -> String {
+    if seconds.is_nan() {
+        return "-".to_owned();
+    }
+
+    return format!("{:.0} ms", seconds * 1000.0);
+}
@@ -1,30 +1,125 @@
+use std::fs::remove_file;
+
 extern crate savefile;
 use savefile::prelude::*;
 
+use super::achievements::Achievements;
+use super::highscore::{Highscore, HighscoreTable};
+use super::history::{History, RoundRecord};
+use super::mode::CLASSIC;
+use super::paths::{path_str, paths};
+use super::profiles::{Profile, Profiles, DEFAULT_PROFILE};
+
+const SAVE_VERSION: u32 = 5;
+
 #[derive(Savefile)]
 pub struct GameSave {
+    // Saves from before profiles kept one player's highscores and history in these,
+    // classic mode separate so saves from before modes still load
     pub highscores: Vec<u32>,
+    #[savefile_versions = "1.."]
+    pub mode_highscores: Vec<HighscoreTable>,
+    #[savefile_versions = "2.."]
+    pub history: Vec<RoundRecord>,
+    #[savefile_versions = "3.."]
+    pub profiles: Vec<Profile>,
+    #[savefile_versions = "3.."]
+    pub active_profile: u32,
 }
 
 impl GameSave {
-    pub fn new() -> Self {
+    // Every profile, with the active one's data taken from what's being played with
+    pub fn new_from_data(
+        profiles: &Profiles,
+        highscore: &Highscore,
+        history: &History,
+        achievements: &Achievements,
+    ) -> Self {
+        let mut profiles = profiles.clone();
+        profiles.store(highscore, history, achievements);
+
+        return GameSave::new_from_profiles(profiles);
+    }
+
+    pub fn new_from_profiles(profiles: Profiles) -> Self {
         return Self {
-            highscores: vec![0; 9],
+            highscores: vec![],
+            mode_highscores: vec![],
+            history: vec![],
+            active_profile: profiles.active as u32,
+            profiles: profiles.list,
         };
     }
 
-    pub fn new_from_data(highscores: Vec<u32>) -> Self {
-        return Self {
-            highscores: highscores,
+    // Older saves become the first profile
+    pub fn into_profiles(self) -> Profiles {
+        if self.profiles.is_empty() {
+            let mut profile = Profile::new(0, DEFAULT_PROFILE);
+            profile.highscores = self.mode_highscores;
+            profile.highscores.retain(|table| table.mode != CLASSIC);
+            profile.highscores.insert(
+                0,
+                HighscoreTable {
+                    mode: CLASSIC.to_owned(),
+                    scores: self.highscores,
+                },
+            );
+            profile.history = self.history;
+
+            return Profiles {
+                list: vec![profile],
+                active: 0,
+            };
+        }
+
+        let active = (self.active_profile as usize).min(self.profiles.len() - 1);
+        return Profiles {
+            list: self.profiles,
+            active,
         };
     }
 
     pub fn save_to_file(&mut self) {
-        save_file("savefile.bin", 0, self).unwrap();
+        let path = &paths().save;
+        if path.exists() {
+            remove_file(path).unwrap();
+        }
+        save_file(&path_str(path), SAVE_VERSION, self).unwrap();
     }
+}
 
+pub fn save_exists() -> bool {
+    return paths().save.exists();
 }
 
 pub fn load_from_file() -> GameSave {
-    return load_file("savefile.bin", 0).unwrap();
-}
\ No newline at end of file
+    return load_file(&path_str(&paths().save), SAVE_VERSION).unwrap();
+}
+
+// Saved profiles, or just the default one if nothing has been saved yet
+pub fn load_profiles() -> Profiles {
+    if save_exists() {
+        return load_from_file().into_profiles();
+    }
+
+    return Profiles::new();
+}
+
+// Highscores and history of the profile played last
+pub fn load_data() -> (Highscore, History) {
+    let mut highscore = Highscore::new();
+    let mut history = History::new();
+    load_profiles().load_active(&mut highscore, &mut history, &mut Achievements::new());
+
+    return (highscore, history);
+}
+
+// Saves the highscores and history as the profile played last, keeping the other profiles
+pub fn save_data(highscore: &Highscore, history: &History) {
+    let mut profiles = load_profiles();
+    let profile = profiles.active_mut();
+    profile.highscores = highscore.tables.clone();
+    profile.history = history.rounds.clone();
+
+    GameSave::new_from_profiles(profiles).save_to_file();
+}
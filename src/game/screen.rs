@@ -0,0 +1,147 @@
+use macroquad::prelude::*;
+
+use super::achievements::Achievements;
+use super::audio::Audio;
+use super::crosshair::Crosshair;
+use super::highscore::Highscore;
+use super::history::History;
+use super::input;
+use super::layout;
+use super::mode_select::ModeSelect;
+use super::pattern::Pattern;
+use super::profiles::Profiles;
+use super::saves::GameSave;
+use super::settings::{GameSettings, Settings};
+
+// What the game does with the screen stack after a screen's update
+pub enum Transition {
+    None,
+    // Puts a screen on top, the current one stays underneath
+    Push(Box<dyn Screen>),
+    // Removes the current screen, going back to the one underneath
+    Pop,
+    // Removes the current screen and gives the one underneath what came of it
+    PopWith(ScreenResult),
+    // Swaps the current screen for another
+    Replace(Box<dyn Screen>),
+    // Back to the main menu at the bottom of the stack
+    Home,
+    Quit,
+}
+
+// What an overlay hands back to the screen that pushed it
+pub enum ScreenResult {
+    // Yes was chosen in a confirmation
+    Confirmed,
+    // Text that was typed in and confirmed with Enter
+    Text(String),
+    Cancelled,
+}
+
+// Everything screens share
+pub struct Context {
+    pub pattern: Pattern,
+    pub mode_select: ModeSelect,
+    pub highscore: Highscore,
+    pub history: History,
+    pub achievements: Achievements,
+    pub settings: Settings,
+    pub audio: Audio,
+    pub crosshair: Crosshair,
+    pub score_color: Color,
+    pub profiles: Profiles,
+    // Set when another profile was picked, its settings are loaded before the next frame
+    pub reload: bool,
+}
+
+impl Context {
+    pub fn new() -> Self {
+        return Self {
+            ..Default::default()
+        };
+    }
+
+    pub fn save(&self) {
+        let mut save = GameSave::new_from_data(&self.profiles, &self.highscore, &self.history, &self.achievements);
+        save.save_to_file();
+    }
+
+    // Colors of the board, crosshair and score
+    pub fn apply_theme(&mut self, options: &GameSettings) {
+        let colors = options.colors;
+        self.pattern.tiles_filled_color = colors.filled;
+        self.pattern.tiles_empty_color = colors.empty;
+        self.pattern.tiles_decoy_color = colors.decoy;
+        self.pattern.tiles_bonus_color = colors.bonus;
+        self.pattern.tiles_time_color = colors.time;
+        self.pattern.tiles_multi_hit_color = colors.multi_hit;
+        self.pattern.tiles_border_color = colors.border;
+        self.pattern.tile_cues = options.tile_cues;
+        self.crosshair.color = colors.crosshair;
+        self.score_color = colors.score;
+    }
+
+    // The accessibility options picked in the settings menu
+    pub fn apply_accessibility(&mut self) {
+        let settings = &self.settings;
+        self.pattern.feedback.reduced_motion = settings.reduced_motion;
+        self.achievements.reduced_motion = settings.reduced_motion;
+        self.pattern.timer_scale = settings.timer_scale;
+        self.pattern.miss_tolerance = settings.miss_tolerance;
+        layout::set_large_text(settings.large_text);
+        input::set_keyboard_layout(settings.keyboard_layout);
+        input::set_hold_to_confirm(settings.hold_to_confirm);
+    }
+
+    // Sets up a round of the mode selected in the mode select, with a random seed if there's none
+    pub fn setup_round(&mut self, seed: Option<u64>) {
+        self.pattern.mode = self.mode_select.selected_mode();
+        self.pattern.curve = self.mode_select.selected_curve();
+        match seed {
+            Some(seed) => self.pattern.setup_with_seed(seed),
+            None => self.pattern.setup(),
+        }
+    }
+}
+
+impl Default for Context {
+    fn default() -> Context {
+        return Context {
+            pattern: Pattern::new(),
+            mode_select: ModeSelect::new(),
+            highscore: Highscore::new(),
+            history: History::new(),
+            achievements: Achievements::new(),
+            settings: Settings::new(),
+            audio: Audio::new(),
+            crosshair: Crosshair::new(),
+            score_color: WHITE,
+            profiles: Profiles::new(),
+            reload: false,
+        };
+    }
+}
+
+// One screen of the game. Only the screen on top of the stack is updated, and it's drawn
+// over the ones underneath it when it's an overlay.
+pub trait Screen {
+    // When the screen is put on the stack
+    fn enter(&mut self, _ctx: &mut Context) {}
+
+    // When the screen is taken off the stack
+    fn exit(&mut self, _ctx: &mut Context) {}
+
+    fn update(&mut self, ctx: &mut Context) -> Transition;
+
+    fn render(&mut self, ctx: &mut Context);
+
+    // When a screen put on top of this one was removed with a result
+    fn resume(&mut self, _ctx: &mut Context, _result: ScreenResult) -> Transition {
+        return Transition::None;
+    }
+
+    // Overlays are drawn on top of the screen underneath instead of replacing it
+    fn is_overlay(&self) -> bool {
+        return false;
+    }
+}
@@ -0,0 +1,681 @@
+use std::path::Path;
+
+use macroquad::prelude::*;
+
+use super::audio::Effect;
+use super::export;
+use super::history::RoundRecord;
+use super::input::{is_action_pressed, Action};
+use super::layout::{self, at, draw_text_centred, Anchor};
+use super::locale::{self, tr, tr_with};
+use super::menu::{Menu, MenuEvent, MenuItem};
+use super::pattern::PatternEvent;
+use super::profiles::{Profiles, MAX_NAME_LENGTH};
+use super::results::{Results, ResultsSelect};
+use super::screen::{Context, Screen, ScreenResult, Transition};
+use super::settings::SettingsSelect;
+use super::statistics::{Statistics, StatisticsAction};
+use super::text::{self, TextStyle};
+
+// Dims the screen behind the pause menu and the other overlays
+const PAUSE_OVERLAY_COLOR: Color = color_u8!(0, 0, 0, 160);
+
+#[derive(Clone, Copy, PartialEq)]
+enum MenuSelect {
+    Run,
+    Highscore,
+    Statistics,
+    Settings,
+    Achievements,
+    Profiles,
+    Close,
+}
+
+pub struct MainMenu {
+    menu: Menu<MenuSelect>,
+    // Language the labels are in, the menu is rebuilt when it's changed in the settings
+    language: String,
+}
+
+impl MainMenu {
+    pub fn new() -> Self {
+        return Self {
+            ..Default::default()
+        };
+    }
+}
+
+impl Screen for MainMenu {
+    fn update(&mut self, ctx: &mut Context) -> Transition {
+        if self.language != locale::language() {
+            *self = MainMenu::new();
+        }
+        // Follows renames and switches in the profiles screen
+        let profile = tr_with("main_menu.profile", &[("name", ctx.profiles.active().name.clone())]);
+        if let Some(item) = self.menu.items.iter_mut().find(|item| item.action == Some(MenuSelect::Profiles)) {
+            item.label = profile;
+        }
+
+        return match self.menu.update() {
+            Some(MenuEvent::Activated(MenuSelect::Run)) => Transition::Push(Box::new(ModeSelectScreen)),
+            Some(MenuEvent::Activated(MenuSelect::Highscore)) => Transition::Push(Box::new(HighscoreScreen)),
+            Some(MenuEvent::Activated(MenuSelect::Statistics)) => {
+                Transition::Push(Box::new(StatisticsScreen::new()))
+            }
+            Some(MenuEvent::Activated(MenuSelect::Settings)) => Transition::Push(Box::new(SettingsScreen)),
+            Some(MenuEvent::Activated(MenuSelect::Achievements)) => Transition::Push(Box::new(AchievementsScreen)),
+            Some(MenuEvent::Activated(MenuSelect::Profiles)) => {
+                Transition::Push(Box::new(ProfilesScreen::new()))
+            }
+            Some(MenuEvent::Activated(MenuSelect::Close)) => Transition::Quit,
+            Some(MenuEvent::Back) | None => Transition::None,
+        };
+    }
+
+    fn render(&mut self, _ctx: &mut Context) {
+        self.menu.render();
+    }
+}
+
+impl Default for MainMenu {
+    fn default() -> MainMenu {
+        let mut menu = Menu::new(
+            &tr("main_menu.title"),
+            vec![
+                MenuItem::new(&tr("main_menu.start"), MenuSelect::Run),
+                MenuItem::new(&tr("main_menu.highscore"), MenuSelect::Highscore),
+                MenuItem::new(&tr("main_menu.statistics"), MenuSelect::Statistics),
+                MenuItem::new(&tr("main_menu.achievements"), MenuSelect::Achievements),
+                MenuItem::new(&tr("main_menu.settings"), MenuSelect::Settings),
+                MenuItem::new(&tr_with("main_menu.profile", &[("name", String::new())]), MenuSelect::Profiles),
+                MenuItem::new(&tr("main_menu.exit"), MenuSelect::Close),
+            ],
+        );
+        // The main menu is the root, there's nothing to go back to
+        menu.back_button = false;
+
+        return MainMenu {
+            menu,
+            language: locale::language(),
+        };
+    }
+}
+
+// The mode list lives in the context so rounds can be started from the command line too
+pub struct ModeSelectScreen;
+
+impl Screen for ModeSelectScreen {
+    fn enter(&mut self, ctx: &mut Context) {
+        ctx.mode_select.setup();
+    }
+
+    fn update(&mut self, ctx: &mut Context) -> Transition {
+        return match ctx.mode_select.update() {
+            Some(MenuEvent::Activated(_)) => {
+                ctx.setup_round(None);
+                Transition::Replace(Box::new(Round))
+            }
+            Some(MenuEvent::Back) => Transition::Pop,
+            None => Transition::None,
+        };
+    }
+
+    fn render(&mut self, ctx: &mut Context) {
+        ctx.mode_select.render();
+    }
+}
+
+// A round of the pattern in the context, set up before the screen is pushed
+pub struct Round;
+
+impl Screen for Round {
+    fn update(&mut self, ctx: &mut Context) -> Transition {
+        if is_action_pressed(Action::Back) {
+            return Transition::Push(Box::new(Pause::new()));
+        }
+
+        ctx.pattern.update();
+        for event in ctx.pattern.events.drain(..) {
+            ctx.audio.play(match event {
+                PatternEvent::Hit => Effect::Hit,
+                PatternEvent::Miss => Effect::Miss,
+                PatternEvent::ComboTier => Effect::ComboTier,
+                PatternEvent::Countdown => Effect::Countdown,
+                PatternEvent::RoundEnd => Effect::RoundEnd,
+                PatternEvent::Beat => Effect::Beat,
+            });
+        }
+        // A miss that ends the round is shown before the results
+        if !ctx.pattern.done || ctx.pattern.feedback.is_shaking() {
+            return Transition::None;
+        }
+
+        let score = ctx.pattern.score;
+        // Assisted rounds have their own table
+        let mode = ctx.pattern.highscore_mode();
+        let new_record = score > ctx.highscore.best(&mode);
+        if !ctx.highscore.score_exist(&mode, score) {
+            ctx.highscore.add_score(&mode, score);
+        }
+
+        let rank = ctx.highscore.rank(&mode, score);
+        let record = RoundRecord::from_pattern(&ctx.pattern);
+        let mut results = Results::new();
+        results.score_color = ctx.score_color;
+        results.setup(&record, rank, new_record);
+        ctx.history.add(record.clone());
+        ctx.achievements.check_round(&record, &ctx.history);
+
+        // Saved right away so finished rounds aren't lost if the game doesn't exit cleanly
+        ctx.save();
+
+        return Transition::Replace(Box::new(ResultsScreen { results }));
+    }
+
+    fn render(&mut self, ctx: &mut Context) {
+        ctx.pattern.render();
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum PauseSelect {
+    Resume,
+    Restart,
+    Menu,
+}
+
+// Sits on top of the round, which stays drawn underneath
+pub struct Pause {
+    menu: Menu<PauseSelect>,
+}
+
+impl Pause {
+    pub fn new() -> Self {
+        return Self {
+            ..Default::default()
+        };
+    }
+}
+
+impl Screen for Pause {
+    fn enter(&mut self, ctx: &mut Context) {
+        ctx.pattern.pause();
+    }
+
+    fn update(&mut self, ctx: &mut Context) -> Transition {
+        return match self.menu.update() {
+            Some(MenuEvent::Activated(PauseSelect::Resume)) | Some(MenuEvent::Back) => {
+                ctx.pattern.resume();
+                Transition::Pop
+            }
+            Some(MenuEvent::Activated(PauseSelect::Restart)) => {
+                ctx.pattern.setup();
+                Transition::Pop
+            }
+            Some(MenuEvent::Activated(PauseSelect::Menu)) => Transition::Home,
+            None => Transition::None,
+        };
+    }
+
+    fn render(&mut self, _ctx: &mut Context) {
+        draw_rectangle(0.0, 0.0, screen_width(), screen_height(), PAUSE_OVERLAY_COLOR);
+        self.menu.render();
+    }
+
+    fn is_overlay(&self) -> bool {
+        return true;
+    }
+}
+
+impl Default for Pause {
+    fn default() -> Pause {
+        return Pause {
+            menu: Menu::new(
+                &tr("pause.title"),
+                vec![
+                    MenuItem::new(&tr("pause.resume"), PauseSelect::Resume),
+                    MenuItem::new(&tr("pause.restart"), PauseSelect::Restart),
+                    MenuItem::new(&tr("pause.menu"), PauseSelect::Menu),
+                ],
+            ),
+        };
+    }
+}
+
+pub struct ResultsScreen {
+    results: Results,
+}
+
+impl Screen for ResultsScreen {
+    fn update(&mut self, ctx: &mut Context) -> Transition {
+        return match self.results.update() {
+            Some(MenuEvent::Activated(ResultsSelect::Retry)) => {
+                ctx.pattern.setup();
+                Transition::Replace(Box::new(Round))
+            }
+            Some(MenuEvent::Activated(ResultsSelect::SaveReplay)) => {
+                self.results.save_replay();
+                Transition::None
+            }
+            Some(MenuEvent::Activated(ResultsSelect::Menu)) | Some(MenuEvent::Back) => Transition::Home,
+            None => Transition::None,
+        };
+    }
+
+    fn render(&mut self, _ctx: &mut Context) {
+        self.results.render();
+    }
+}
+
+// The highscores live in the context, rounds add to them
+pub struct HighscoreScreen;
+
+impl Screen for HighscoreScreen {
+    fn enter(&mut self, ctx: &mut Context) {
+        ctx.highscore.setup();
+    }
+
+    fn update(&mut self, ctx: &mut Context) -> Transition {
+        if ctx.highscore.update() {
+            return Transition::Pop;
+        }
+
+        return Transition::None;
+    }
+
+    fn render(&mut self, ctx: &mut Context) {
+        ctx.highscore.render();
+    }
+}
+
+pub struct StatisticsScreen {
+    statistics: Statistics,
+}
+
+impl StatisticsScreen {
+    pub fn new() -> Self {
+        return Self {
+            statistics: Statistics::new(),
+        };
+    }
+}
+
+impl Screen for StatisticsScreen {
+    fn enter(&mut self, ctx: &mut Context) {
+        self.statistics.setup(&ctx.history);
+    }
+
+    fn update(&mut self, ctx: &mut Context) -> Transition {
+        match self.statistics.update(&ctx.history) {
+            Some(StatisticsAction::Export) => {
+                let dir = Path::new(export::EXPORT_DIR);
+                self.statistics.status = match export::export(dir, &ctx.highscore, &ctx.history) {
+                    Ok(()) => tr_with("statistics.exported", &[("path", dir.display().to_string())]),
+                    Err(err) => tr_with("statistics.export_failed", &[("error", err.to_string())]),
+                };
+            }
+            Some(StatisticsAction::Import) => {
+                let path = Path::new(export::IMPORT_DIR);
+                let status = match export::import(path, &mut ctx.highscore, &mut ctx.history) {
+                    Ok(summary) => {
+                        ctx.save();
+                        tr_with(
+                            "statistics.imported",
+                            &[
+                                ("scores", summary.scores.to_string()),
+                                ("rounds", summary.rounds.to_string()),
+                                ("duplicates", summary.duplicates.to_string()),
+                            ],
+                        )
+                    }
+                    Err(err) => tr_with("statistics.import_failed", &[("error", err.to_string())]),
+                };
+                self.statistics.setup(&ctx.history);
+                self.statistics.status = status;
+            }
+            Some(StatisticsAction::Back) => return Transition::Pop,
+            None => {}
+        }
+
+        return Transition::None;
+    }
+
+    fn render(&mut self, _ctx: &mut Context) {
+        self.statistics.render();
+    }
+}
+
+// Unlocked achievements live in the context, rounds add to them
+pub struct AchievementsScreen;
+
+impl Screen for AchievementsScreen {
+    fn enter(&mut self, ctx: &mut Context) {
+        ctx.achievements.setup();
+    }
+
+    fn update(&mut self, ctx: &mut Context) -> Transition {
+        if ctx.achievements.update() {
+            return Transition::Pop;
+        }
+
+        return Transition::None;
+    }
+
+    fn render(&mut self, ctx: &mut Context) {
+        ctx.achievements.render();
+    }
+}
+
+// The settings are read once on load, the menu writes changes back to the file itself
+pub struct SettingsScreen;
+
+impl Screen for SettingsScreen {
+    fn update(&mut self, ctx: &mut Context) -> Transition {
+        match ctx.settings.update() {
+            // Screens that stay open pick up the language when they are entered again
+            Some(MenuEvent::Activated(SettingsSelect::Language)) => {
+                text::set_font(locale::load_font());
+            }
+            Some(MenuEvent::Activated(SettingsSelect::Theme))
+            | Some(MenuEvent::Activated(SettingsSelect::TileCues)) => {
+                let options = ctx.settings.themed_options();
+                ctx.apply_theme(&options);
+            }
+            Some(MenuEvent::Activated(SettingsSelect::TimerBar)) => {
+                ctx.pattern.display_timer_bar = ctx.settings.timer_bar;
+            }
+            Some(MenuEvent::Activated(SettingsSelect::Effects)) => {
+                ctx.pattern.feedback.enabled = ctx.settings.effects;
+            }
+            Some(MenuEvent::Activated(SettingsSelect::UiScale)) => {
+                layout::set_ui_scale(ctx.settings.ui_scale);
+            }
+            // A hit is played at the new volume to hear the change
+            Some(MenuEvent::Activated(SettingsSelect::MasterVolume))
+            | Some(MenuEvent::Activated(SettingsSelect::EffectsVolume))
+            | Some(MenuEvent::Activated(SettingsSelect::Mute)) => {
+                ctx.audio.master_volume = ctx.settings.master_volume;
+                ctx.audio.effects_volume = ctx.settings.effects_volume;
+                ctx.audio.muted = ctx.settings.muted;
+                ctx.audio.play(Effect::Hit);
+            }
+            Some(MenuEvent::Activated(SettingsSelect::ReducedMotion))
+            | Some(MenuEvent::Activated(SettingsSelect::LargeText))
+            | Some(MenuEvent::Activated(SettingsSelect::TimerScale))
+            | Some(MenuEvent::Activated(SettingsSelect::MissTolerance))
+            | Some(MenuEvent::Activated(SettingsSelect::KeyboardLayout))
+            | Some(MenuEvent::Activated(SettingsSelect::HoldToConfirm)) => {
+                ctx.apply_accessibility();
+            }
+            Some(MenuEvent::Back) => return Transition::Pop,
+            None => {}
+        }
+
+        return Transition::None;
+    }
+
+    fn render(&mut self, ctx: &mut Context) {
+        ctx.settings.render();
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum ProfileSelect {
+    Switch(usize),
+    New,
+    Rename,
+    Delete,
+}
+
+// Picks, creates, renames and deletes profiles. Names are typed in and deletes confirmed in overlays.
+pub struct ProfilesScreen {
+    menu: Menu<ProfileSelect>,
+    // What the overlay on top was opened for
+    pending: Option<ProfileSelect>,
+    status: String,
+}
+
+impl ProfilesScreen {
+    pub fn new() -> Self {
+        return Self {
+            ..Default::default()
+        };
+    }
+
+    // Labels follow the profiles, the selection stays where it was
+    fn rebuild(&mut self, profiles: &Profiles) {
+        let mut items: Vec<MenuItem<ProfileSelect>> = profiles
+            .list
+            .iter()
+            .enumerate()
+            .map(|(i, profile)| {
+                let label = if i == profiles.active {
+                    tr_with("profiles.active", &[("name", profile.name.clone())])
+                } else {
+                    profile.name.clone()
+                };
+                return MenuItem::new(&label, ProfileSelect::Switch(i));
+            })
+            .collect();
+        items.push(MenuItem::new(&tr("profiles.new"), ProfileSelect::New));
+        items.push(MenuItem::new(&tr("profiles.rename"), ProfileSelect::Rename));
+        let mut delete = MenuItem::new(&tr("profiles.delete"), ProfileSelect::Delete);
+        delete.enabled = profiles.list.len() > 1;
+        items.push(delete);
+
+        let selected = self.menu.selected();
+        self.menu = Menu::new(&tr("profiles.title"), items);
+        // Higher up than other menus to leave room for more profiles
+        self.menu.top = -150.0;
+        self.menu.select(selected.min(self.menu.items.len() - 1));
+    }
+
+    fn open(&mut self, action: ProfileSelect, overlay: Box<dyn Screen>) -> Transition {
+        self.pending = Some(action);
+        self.status = String::new();
+        return Transition::Push(overlay);
+    }
+
+    // Creates or renames with the typed name, asking again with the error when it can't be used
+    fn name_entered(&mut self, ctx: &mut Context, action: ProfileSelect, name: String) -> Transition {
+        let result = match action {
+            ProfileSelect::New => ctx.profiles.create(&name),
+            _ => ctx.profiles.rename(ctx.profiles.active, &name).map(|()| ctx.profiles.active),
+        };
+        match result {
+            Ok(i) => {
+                self.rebuild(&ctx.profiles);
+                self.menu.select(i);
+                ctx.save();
+                return Transition::None;
+            }
+            Err(err) => {
+                let title = self.menu.items[self.menu.selected()].label.clone();
+                return self.open(action, Box::new(NameEntry::new(&title, name, err)));
+            }
+        }
+    }
+}
+
+impl Screen for ProfilesScreen {
+    fn enter(&mut self, ctx: &mut Context) {
+        self.rebuild(&ctx.profiles);
+        self.menu.select(ctx.profiles.active);
+    }
+
+    fn update(&mut self, ctx: &mut Context) -> Transition {
+        match self.menu.update() {
+            Some(MenuEvent::Activated(ProfileSelect::Switch(i))) => {
+                if i != ctx.profiles.active {
+                    ctx.profiles.switch(i, &mut ctx.highscore, &mut ctx.history, &mut ctx.achievements);
+                    ctx.reload = true;
+                }
+                return Transition::Pop;
+            }
+            Some(MenuEvent::Activated(ProfileSelect::New)) => {
+                let entry = NameEntry::new(&tr("profiles.new"), String::new(), String::new());
+                return self.open(ProfileSelect::New, Box::new(entry));
+            }
+            Some(MenuEvent::Activated(ProfileSelect::Rename)) => {
+                let name = ctx.profiles.active().name.clone();
+                let entry = NameEntry::new(&tr("profiles.rename"), name, String::new());
+                return self.open(ProfileSelect::Rename, Box::new(entry));
+            }
+            Some(MenuEvent::Activated(ProfileSelect::Delete)) => {
+                let question = tr_with("profiles.confirm_delete", &[("name", ctx.profiles.active().name.clone())]);
+                return self.open(ProfileSelect::Delete, Box::new(Confirm::new(&question, &tr("profiles.delete"))));
+            }
+            Some(MenuEvent::Back) => return Transition::Pop,
+            None => {}
+        }
+
+        return Transition::None;
+    }
+
+    fn render(&mut self, _ctx: &mut Context) {
+        self.menu.render();
+
+        let status = at(Anchor::BottomLeft, 250.0, -32.0);
+        draw_text_ex(&self.status, status.x, status.y, text::params(TextStyle::Small));
+    }
+
+    fn resume(&mut self, ctx: &mut Context, result: ScreenResult) -> Transition {
+        match (self.pending.take(), result) {
+            (Some(ProfileSelect::Delete), ScreenResult::Confirmed) => {
+                let name = ctx.profiles.active().name.clone();
+                self.status = match ctx.profiles.delete_active(&mut ctx.highscore, &mut ctx.history, &mut ctx.achievements) {
+                    Ok(()) => {
+                        ctx.reload = true;
+                        tr_with("profiles.deleted", &[("name", name)])
+                    }
+                    Err(err) => err,
+                };
+                self.rebuild(&ctx.profiles);
+            }
+            (Some(action), ScreenResult::Text(name)) => return self.name_entered(ctx, action, name),
+            _ => {}
+        }
+
+        return Transition::None;
+    }
+}
+
+impl Default for ProfilesScreen {
+    fn default() -> ProfilesScreen {
+        return ProfilesScreen {
+            menu: Menu::default(),
+            pending: None,
+            status: String::new(),
+        };
+    }
+}
+
+// Typing a name over the screen underneath, Enter to confirm and Escape to cancel.
+// There's no menu, some keyboard layouts use Backspace to go back.
+pub struct NameEntry {
+    title: String,
+    name: String,
+    // Why the name given before couldn't be used, or how to type one
+    message: String,
+}
+
+impl NameEntry {
+    pub fn new(title: &str, name: String, error: String) -> Self {
+        let message = if error.is_empty() { tr("profiles.editing") } else { error };
+        return Self {
+            title: title.to_owned(),
+            name,
+            message,
+        };
+    }
+}
+
+impl Screen for NameEntry {
+    fn enter(&mut self, _ctx: &mut Context) {
+        // Keys typed to get here aren't part of the name
+        while get_char_pressed().is_some() {}
+    }
+
+    fn update(&mut self, _ctx: &mut Context) -> Transition {
+        while let Some(c) = get_char_pressed() {
+            if !c.is_control() && self.name.chars().count() < MAX_NAME_LENGTH {
+                self.name.push(c);
+            }
+        }
+        if is_key_pressed(KeyCode::Backspace) {
+            self.name.pop();
+        }
+        if is_key_pressed(KeyCode::Escape) {
+            return Transition::PopWith(ScreenResult::Cancelled);
+        }
+        if is_key_pressed(KeyCode::Enter) || is_key_pressed(KeyCode::KpEnter) {
+            return Transition::PopWith(ScreenResult::Text(self.name.clone()));
+        }
+
+        return Transition::None;
+    }
+
+    fn render(&mut self, _ctx: &mut Context) {
+        draw_rectangle(0.0, 0.0, screen_width(), screen_height(), PAUSE_OVERLAY_COLOR);
+
+        let title = at(Anchor::Centre, 0.0, -80.0);
+        draw_text_centred(&self.title, title.x, title.y, text::params(TextStyle::Title));
+        let prompt = tr_with("profiles.name", &[("name", format!("{}_", self.name))]);
+        let name = at(Anchor::Centre, 0.0, 0.0);
+        draw_text_centred(&prompt, name.x, name.y, text::params(TextStyle::MenuItem));
+        let message = at(Anchor::Centre, 0.0, 60.0);
+        draw_text_centred(&self.message, message.x, message.y, text::params(TextStyle::Small));
+    }
+
+    fn is_overlay(&self) -> bool {
+        return true;
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum ConfirmSelect {
+    Yes,
+    No,
+}
+
+// Asks before doing something that can't be undone, over the screen underneath
+pub struct Confirm {
+    menu: Menu<ConfirmSelect>,
+}
+
+impl Confirm {
+    // `yes` names what's done, going back or choosing Cancel leaves it
+    pub fn new(question: &str, yes: &str) -> Self {
+        let mut menu = Menu::new(
+            question,
+            vec![
+                MenuItem::new(yes, ConfirmSelect::Yes),
+                MenuItem::new(&tr("button.cancel"), ConfirmSelect::No),
+            ],
+        );
+        // Starts on Cancel so a double press doesn't go through
+        menu.select(1);
+        return Self { menu };
+    }
+}
+
+impl Screen for Confirm {
+    fn update(&mut self, _ctx: &mut Context) -> Transition {
+        return match self.menu.update() {
+            Some(MenuEvent::Activated(ConfirmSelect::Yes)) => Transition::PopWith(ScreenResult::Confirmed),
+            Some(MenuEvent::Activated(ConfirmSelect::No)) | Some(MenuEvent::Back) => {
+                Transition::PopWith(ScreenResult::Cancelled)
+            }
+            None => Transition::None,
+        };
+    }
+
+    fn render(&mut self, _ctx: &mut Context) {
+        draw_rectangle(0.0, 0.0, screen_width(), screen_height(), PAUSE_OVERLAY_COLOR);
+        self.menu.render();
+    }
+
+    fn is_overlay(&self) -> bool {
+        return true;
+    }
+}
@@ -1,47 +1,381 @@
-use std::{fs, path::Path};
+use std::{fs, process::exit};
 
 use macroquad::prelude::*;
 
 use serde_derive::Deserialize;
-use toml;
 
+use super::crosshair::CrosshairStyle;
+use super::difficulty::{load_curves, DifficultyCurve};
+use super::input::KeyboardLayout;
+use super::layout::{at, Anchor};
+use super::locale::{self, tr, tr_with, FALLBACK_LANGUAGE};
+use super::menu::{Menu, MenuEvent, MenuItem};
+use super::mode::GameMode;
+use super::paths::paths;
+use super::profiles::config_path;
+use super::text::{self, StyleOverride, TextStyle};
+use super::theme::{Theme, ThemePreset};
 
 #[derive(Deserialize)]
 struct GameOptions {
-    pub tile_filled: Vec<u8>,
-    pub tile_empty: Vec<u8>,
-    pub tile_border: Vec<u8>,
-    pub crosshair: Vec<u8>,
-    pub score: Vec<u8>,
+    #[serde(default = "default_language")]
+    pub language: String,
+    #[serde(default = "default_theme")]
+    pub theme: ThemePreset,
+    #[serde(default)]
+    pub tile_cues: bool,
+    #[serde(default)]
+    pub tile_filled: Option<Vec<u8>>,
+    #[serde(default)]
+    pub tile_empty: Option<Vec<u8>>,
+    #[serde(default)]
+    pub tile_border: Option<Vec<u8>>,
+    #[serde(default)]
+    pub tile_decoy: Option<Vec<u8>>,
+    #[serde(default)]
+    pub tile_bonus: Option<Vec<u8>>,
+    #[serde(default)]
+    pub tile_time: Option<Vec<u8>>,
+    #[serde(default)]
+    pub tile_multi_hit: Option<Vec<u8>>,
+    #[serde(default)]
+    pub crosshair: Option<Vec<u8>>,
+    #[serde(default = "default_crosshair_style")]
+    pub crosshair_style: CrosshairStyle,
+    #[serde(default = "default_crosshair_size")]
+    pub crosshair_size: f32,
+    #[serde(default = "default_crosshair_thickness")]
+    pub crosshair_thickness: f32,
+    #[serde(default = "default_crosshair_gap")]
+    pub crosshair_gap: f32,
+    #[serde(default)]
+    pub crosshair_outline: f32,
+    #[serde(default)]
+    pub crosshair_outline_color: Option<Vec<u8>>,
+    #[serde(default)]
+    pub crosshair_image: Option<String>,
+    #[serde(default)]
+    pub score: Option<Vec<u8>>,
+    #[serde(default)]
+    pub text_styles: Vec<TextStyleOptions>,
     pub scale: f64,
+    #[serde(default = "default_round_duration")]
+    pub round_duration: f32,
+    #[serde(default = "default_true")]
+    pub timer_bar: bool,
+    #[serde(default = "default_true")]
+    pub effects: bool,
+    #[serde(default)]
+    pub modes: Vec<GameMode>,
+    #[serde(default = "default_window_mode")]
+    pub window_mode: WindowMode,
+    #[serde(default = "default_resolution")]
+    pub resolution: Vec<i32>,
+    #[serde(default)]
+    pub resizable: bool,
+    #[serde(default = "default_ui_scale")]
+    pub ui_scale: f32,
+    #[serde(default = "default_volume")]
+    pub master_volume: f32,
+    #[serde(default = "default_volume")]
+    pub effects_volume: f32,
+    #[serde(default)]
+    pub muted: bool,
+    #[serde(default)]
+    pub reduced_motion: bool,
+    #[serde(default)]
+    pub large_text: bool,
+    #[serde(default = "default_timer_scale")]
+    pub timer_scale: f32,
+    #[serde(default)]
+    pub miss_tolerance: u32,
+    #[serde(default = "default_keyboard_layout")]
+    pub keyboard_layout: KeyboardLayout,
+    #[serde(default)]
+    pub hold_to_confirm: bool,
+}
+
+// Changes to one of the text styles, anything left out keeps the default
+#[derive(Deserialize)]
+struct TextStyleOptions {
+    pub style: TextStyle,
+    #[serde(default)]
+    pub font: Option<String>,
+    #[serde(default)]
+    pub size: Option<u16>,
+    #[serde(default)]
+    pub color: Option<Vec<u8>>,
+}
+
+#[derive(Deserialize, Clone, Copy, PartialEq)]
+pub enum WindowMode {
+    Fullscreen,
+    Windowed,
+}
+
+impl GameOptions {
+    // Colors are RGBA and the resolution is a width and height, anything else would panic when they're read.
+    // A ui_scale of 0 would draw nothing at all, and a timer_scale below 1 would make rounds harder, not easier.
+    // Modes are checked as they're written, before they replace the built in ones.
+    fn check(&self) -> Result<(), String> {
+        if self.resolution.len() != 2 || self.resolution.iter().any(|&size| size <= 0) {
+            return Err("resolution needs a width and height above 0".to_owned());
+        }
+        if self.ui_scale <= 0.0 {
+            return Err("ui_scale needs to be above 0".to_owned());
+        }
+        if self.timer_scale < 1.0 {
+            return Err("timer_scale needs to be at least 1".to_owned());
+        }
+        if self.crosshair_size <= 0.0 || self.crosshair_thickness <= 0.0 {
+            return Err("crosshair_size and crosshair_thickness need to be above 0".to_owned());
+        }
+        if self.crosshair_gap < 0.0 || self.crosshair_outline < 0.0 {
+            return Err("crosshair_gap and crosshair_outline can't be negative".to_owned());
+        }
+        for (name, volume) in [("master_volume", self.master_volume), ("effects_volume", self.effects_volume)] {
+            if !(0.0..=1.0).contains(&volume) {
+                return Err(format!("{} needs to be between 0 and 1", name));
+            }
+        }
+        if self.round_duration <= 0.0 {
+            return Err("round_duration needs to be above 0".to_owned());
+        }
+        for (i, mode) in self.modes.iter().enumerate() {
+            if self.modes[..i].iter().any(|other| other.name == mode.name) {
+                return Err(format!("mode \"{}\" is defined twice", mode.name));
+            }
+            if mode.duration <= 0.0 {
+                return Err(format!("mode \"{}\" needs a duration above 0", mode.name));
+            }
+            if mode.bpm < 0.0 {
+                return Err(format!("mode \"{}\" can't have a negative bpm", mode.name));
+            }
+        }
+
+        let colors = [
+            ("tile_filled", self.tile_filled.as_ref()),
+            ("tile_empty", self.tile_empty.as_ref()),
+            ("tile_border", self.tile_border.as_ref()),
+            ("tile_decoy", self.tile_decoy.as_ref()),
+            ("tile_bonus", self.tile_bonus.as_ref()),
+            ("tile_time", self.tile_time.as_ref()),
+            ("tile_multi_hit", self.tile_multi_hit.as_ref()),
+            ("crosshair", self.crosshair.as_ref()),
+            ("crosshair_outline_color", self.crosshair_outline_color.as_ref()),
+            ("score", self.score.as_ref()),
+        ];
+        for (name, color) in colors {
+            if let Some(color) = color {
+                if color.len() != 4 {
+                    return Err(format!("{} needs 4 values (RGBA), got {}", name, color.len()));
+                }
+            }
+        }
+        for style in &self.text_styles {
+            if style.size == Some(0) {
+                return Err("text_styles need a size above 0".to_owned());
+            }
+            if let Some(color) = &style.color {
+                if color.len() != 4 {
+                    return Err(format!("text_styles colors need 4 values (RGBA), got {}", color.len()));
+                }
+            }
+        }
+
+        return Ok(());
+    }
+}
+
+fn default_round_duration() -> f32 {
+    return ROUND_DURATION;
+}
+
+fn default_window_mode() -> WindowMode {
+    return WindowMode::Fullscreen;
+}
+
+fn default_resolution() -> Vec<i32> {
+    return vec![RESOLUTION.0, RESOLUTION.1];
+}
+
+fn default_ui_scale() -> f32 {
+    return 1.0;
+}
+
+fn default_language() -> String {
+    return FALLBACK_LANGUAGE.to_owned();
+}
+
+fn default_theme() -> ThemePreset {
+    return ThemePreset::Default;
+}
+
+fn default_crosshair_style() -> CrosshairStyle {
+    return CrosshairStyle::Cross;
+}
+
+fn default_crosshair_size() -> f32 {
+    return 5.0;
+}
+
+fn default_crosshair_thickness() -> f32 {
+    return 2.0;
+}
+
+fn default_crosshair_gap() -> f32 {
+    return 3.0;
+}
+
+fn default_volume() -> f32 {
+    return 1.0;
+}
+
+fn default_timer_scale() -> f32 {
+    return 1.0;
+}
+
+fn default_keyboard_layout() -> KeyboardLayout {
+    return KeyboardLayout::Default;
+}
+
+fn default_true() -> bool {
+    return true;
+}
+
+fn color_or(color: &Option<Vec<u8>>, default: Color) -> Color {
+    match color {
+        Some(color) => return color_u8!(color[0], color[1], color[2], color[3]),
+        None => return default,
+    }
 }
 
 pub struct GameSettings {
-    pub filled_color: Color,
-    pub empty_color: Color,
-    pub border_color: Color,
-    pub crosshair_color: Color,
-    pub score_color: Color,
+    pub language: String,
+    pub theme: ThemePreset,
+    // The theme's colors with the ones set in the settings on top
+    pub colors: Theme,
+    pub tile_cues: bool,
+    // Set colors that are hard to tell apart
+    pub contrast_warnings: Vec<String>,
+    pub crosshair_style: CrosshairStyle,
+    pub crosshair_size: f32,
+    pub crosshair_thickness: f32,
+    pub crosshair_gap: f32,
+    pub crosshair_outline: f32,
+    pub crosshair_outline_color: Color,
+    pub crosshair_image: Option<String>,
+    pub text_styles: Vec<StyleOverride>,
     pub scale: f32,
+    pub timer_bar: bool,
+    pub effects: bool,
+    pub modes: Vec<GameMode>,
+    pub curves: Vec<DifficultyCurve>,
+    pub window_mode: WindowMode,
+    pub resolution: (i32, i32),
+    pub resizable: bool,
+    pub ui_scale: f32,
+    pub master_volume: f32,
+    pub effects_volume: f32,
+    pub muted: bool,
+    // No shakes or popups
+    pub reduced_motion: bool,
+    pub large_text: bool,
+    // Round timers are multiplied by it, rounds above 1 go into their own highscore table
+    pub timer_scale: f32,
+    // Wrong clicks a round survives, rounds above 0 go into their own highscore table
+    pub miss_tolerance: u32,
+    pub keyboard_layout: KeyboardLayout,
+    pub hold_to_confirm: bool,
 }
 
-const TILE_FILLED_COLOR: Color  = color_u8!(45, 55, 65, 255);
-const TILE_EMPTY_COLOR: Color   = color_u8!(40, 130, 115, 255);
-const TILE_BORDER_COLOR: Color  = color_u8!(255, 255, 255, 255);
-const CROSSHAIR_COLOR: Color    = color_u8!(255, 0, 0, 255);
-const SCORE_COLOR: Color        = color_u8!(0, 255, 0, 255);
+const CROSSHAIR_OUTLINE_COLOR: Color = color_u8!(0, 0, 0, 255);
+const ROUND_DURATION: f32       = 30.0;
+const RESOLUTION: (i32, i32)    = (1280, 720);
 
+// The language is set when it's read, a String can't be made in a const
 const DEFAULT_GAME_OPTIONS: GameSettings = GameSettings{
-    filled_color: TILE_FILLED_COLOR,
-    empty_color: TILE_EMPTY_COLOR,
-    border_color: TILE_BORDER_COLOR,
-    crosshair_color: CROSSHAIR_COLOR,
-    score_color: SCORE_COLOR,
-    scale: 1.0
+    language: String::new(),
+    theme: ThemePreset::Default,
+    colors: ThemePreset::Default.theme(),
+    tile_cues: false,
+    contrast_warnings: Vec::new(),
+    crosshair_style: CrosshairStyle::Cross,
+    crosshair_size: 5.0,
+    crosshair_thickness: 2.0,
+    crosshair_gap: 3.0,
+    crosshair_outline: 0.0,
+    crosshair_outline_color: CROSSHAIR_OUTLINE_COLOR,
+    crosshair_image: None,
+    text_styles: Vec::new(),
+    scale: 1.0,
+    timer_bar: true,
+    effects: true,
+    modes: Vec::new(),
+    curves: Vec::new(),
+    window_mode: WindowMode::Fullscreen,
+    resolution: RESOLUTION,
+    resizable: false,
+    ui_scale: 1.0,
+    master_volume: 1.0,
+    effects_volume: 1.0,
+    muted: false,
+    reduced_motion: false,
+    large_text: false,
+    timer_scale: 1.0,
+    miss_tolerance: 0,
+    keyboard_layout: KeyboardLayout::Default,
+    hold_to_confirm: false,
 };
 
 
-pub struct Settings {}
+// UI scales the settings screen steps through
+const UI_SCALES: [f32; 5] = [0.75, 1.0, 1.25, 1.5, 2.0];
+// And volumes
+const VOLUMES: [f32; 5] = [0.0, 0.25, 0.5, 0.75, 1.0];
+// And the accessibility assists
+const TIMER_SCALES: [f32; 4] = [1.0, 1.5, 2.0, 3.0];
+const MISS_TOLERANCES: [u32; 4] = [0, 1, 3, 5];
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum SettingsSelect {
+    Language,
+    Theme,
+    TileCues,
+    TimerBar,
+    Effects,
+    UiScale,
+    MasterVolume,
+    EffectsVolume,
+    Mute,
+    ReducedMotion,
+    LargeText,
+    TimerScale,
+    MissTolerance,
+    KeyboardLayout,
+    HoldToConfirm,
+}
+
+pub struct Settings {
+    // Code of the language, the name of its file in res/lang
+    pub language: String,
+    pub theme: ThemePreset,
+    pub tile_cues: bool,
+    pub timer_bar: bool,
+    pub effects: bool,
+    pub ui_scale: f32,
+    pub master_volume: f32,
+    pub effects_volume: f32,
+    pub muted: bool,
+    pub reduced_motion: bool,
+    pub large_text: bool,
+    pub timer_scale: f32,
+    pub miss_tolerance: u32,
+    pub keyboard_layout: KeyboardLayout,
+    pub hold_to_confirm: bool,
+    status: String,
+    menu: Menu<SettingsSelect>,
+}
 
 impl Settings {
     pub fn new() -> Self {
@@ -49,153 +383,493 @@ impl Settings {
             ..Default::default()
         };
     }
+
+    pub fn setup(&mut self, options: &GameSettings) {
+        self.language = options.language.clone();
+        self.theme = options.theme;
+        self.tile_cues = options.tile_cues;
+        self.timer_bar = options.timer_bar;
+        self.effects = options.effects;
+        self.ui_scale = options.ui_scale;
+        self.master_volume = options.master_volume;
+        self.effects_volume = options.effects_volume;
+        self.muted = options.muted;
+        self.reduced_motion = options.reduced_motion;
+        self.large_text = options.large_text;
+        self.timer_scale = options.timer_scale;
+        self.miss_tolerance = options.miss_tolerance;
+        self.keyboard_layout = options.keyboard_layout;
+        self.hold_to_confirm = options.hold_to_confirm;
+        self.status = options.contrast_warnings.first().cloned().unwrap_or_default();
+        self.menu = Settings::menu();
+        self.update_labels();
+    }
+
+    // Built again when the language changes, the items are labelled by `update_labels`
+    fn menu() -> Menu<SettingsSelect> {
+        let mut key_bindings = Menu::new(
+            &tr("key_bindings.title"),
+            vec![
+                MenuItem::disabled(&tr("key_bindings.select")),
+                MenuItem::disabled(&tr("key_bindings.back")),
+                MenuItem::disabled(&tr("key_bindings.up")),
+                MenuItem::disabled(&tr("key_bindings.down")),
+                MenuItem::disabled(&tr("key_bindings.pause")),
+                MenuItem::disabled(&tr("key_bindings.hide_info")),
+                MenuItem::disabled(&tr("key_bindings.hide_grid")),
+            ],
+        );
+        key_bindings.top = -150.0;
+        key_bindings.spacing = 30.0;
+        key_bindings.style = TextStyle::Body;
+
+        let mut accessibility = Menu::new(
+            &tr("settings.accessibility"),
+            vec![
+                MenuItem::new("", SettingsSelect::ReducedMotion),
+                MenuItem::new("", SettingsSelect::LargeText),
+                MenuItem::new("", SettingsSelect::TimerScale),
+                MenuItem::new("", SettingsSelect::MissTolerance),
+                MenuItem::new("", SettingsSelect::KeyboardLayout),
+                MenuItem::new("", SettingsSelect::HoldToConfirm),
+            ],
+        );
+        accessibility.top = -150.0;
+
+        let mut menu = Menu::new(
+            &tr("settings.title"),
+            vec![
+                MenuItem::new("", SettingsSelect::Language),
+                MenuItem::new("", SettingsSelect::Theme),
+                MenuItem::new("", SettingsSelect::TileCues),
+                MenuItem::new("", SettingsSelect::TimerBar),
+                MenuItem::new("", SettingsSelect::Effects),
+                MenuItem::new("", SettingsSelect::UiScale),
+                MenuItem::new("", SettingsSelect::MasterVolume),
+                MenuItem::new("", SettingsSelect::EffectsVolume),
+                MenuItem::new("", SettingsSelect::Mute),
+                MenuItem::submenu(&tr("settings.accessibility"), accessibility),
+                MenuItem::submenu(&tr("key_bindings.title"), key_bindings),
+            ],
+        );
+
+        menu.top = -150.0;
+
+        return menu;
+    }
+
+    fn update_labels(&mut self) {
+        // Taken out while relabelling so the labels can be read off self
+        let mut menu = std::mem::take(&mut self.menu);
+        for item in &mut menu.items {
+            if let Some(action) = item.action {
+                item.label = self.label(action);
+            }
+            if let Some(submenu) = &mut item.submenu {
+                for item in &mut submenu.items {
+                    if let Some(action) = item.action {
+                        item.label = self.label(action);
+                    }
+                }
+            }
+        }
+        self.menu = menu;
+    }
+
+    fn label(&self, action: SettingsSelect) -> String {
+        let on_off = |on: bool| ("value", tr(if on { "settings.on" } else { "settings.off" }));
+        let percent = |value: f32| ("percent", format!("{:.0}", value * 100.0));
+        match action {
+            SettingsSelect::Language => {
+                let name = locale::languages()
+                    .into_iter()
+                    .find(|language| language.code == self.language)
+                    .map_or_else(|| self.language.clone(), |language| language.name);
+                return tr_with("settings.language", &[("language", name)]);
+            }
+            SettingsSelect::Theme => return tr_with("settings.theme", &[("theme", self.theme.name().to_owned())]),
+            SettingsSelect::TileCues => return tr_with("settings.tile_cues", &[on_off(self.tile_cues)]),
+            SettingsSelect::TimerBar => return tr_with("settings.timer_bar", &[on_off(self.timer_bar)]),
+            SettingsSelect::Effects => return tr_with("settings.effects", &[on_off(self.effects)]),
+            SettingsSelect::UiScale => return tr_with("settings.ui_scale", &[percent(self.ui_scale)]),
+            SettingsSelect::MasterVolume => return tr_with("settings.master_volume", &[percent(self.master_volume)]),
+            SettingsSelect::EffectsVolume => {
+                return tr_with("settings.effects_volume", &[percent(self.effects_volume)])
+            }
+            SettingsSelect::Mute => {
+                let value = if self.muted { tr("settings.muted") } else { tr("settings.on") };
+                return tr_with("settings.sound", &[("value", value)]);
+            }
+            SettingsSelect::ReducedMotion => return tr_with("settings.reduced_motion", &[on_off(self.reduced_motion)]),
+            SettingsSelect::LargeText => return tr_with("settings.large_text", &[on_off(self.large_text)]),
+            SettingsSelect::TimerScale => return tr_with("settings.timer_scale", &[percent(self.timer_scale)]),
+            SettingsSelect::MissTolerance => {
+                return tr_with("settings.miss_tolerance", &[("misses", self.miss_tolerance.to_string())])
+            }
+            SettingsSelect::KeyboardLayout => {
+                return tr_with("settings.keyboard_layout", &[("layout", self.keyboard_layout.name().to_owned())])
+            }
+            SettingsSelect::HoldToConfirm => {
+                return tr_with("settings.hold_to_confirm", &[on_off(self.hold_to_confirm)])
+            }
+        }
+    }
     
+    // The options with the theme picked in the menu, also when there is no config file to save it to
+    pub fn themed_options(&self) -> GameSettings {
+        let mut options = Settings::read_file_options().unwrap_or(DEFAULT_GAME_OPTIONS);
+        if options.theme != self.theme {
+            options.theme = self.theme;
+            options.colors = self.theme.theme();
+        }
+        options.tile_cues = self.tile_cues;
+
+        return options;
+    }
+
     pub fn load_options() -> GameSettings {
-        if Path::new("settings.toml").exists() {
-            let options_str = fs::read_to_string("./settings.toml").unwrap();
-            let game_options: GameOptions = toml::from_str(&*options_str).unwrap();
-
-            return GameSettings {
-                filled_color: color_u8!(
-                    game_options.tile_filled[0],
-                    game_options.tile_filled[1],
-                    game_options.tile_filled[2],
-                    game_options.tile_filled[3]
-                ),
-                empty_color: color_u8!(
-                    game_options.tile_empty[0],
-                    game_options.tile_empty[1],
-                    game_options.tile_empty[2],
-                    game_options.tile_empty[3]
-                ),
-                border_color: color_u8!(
-                    game_options.tile_border[0],
-                    game_options.tile_border[1],
-                    game_options.tile_border[2],
-                    game_options.tile_border[3]
-                ),
-                crosshair_color: color_u8!(
-                    game_options.crosshair[0],
-                    game_options.crosshair[1],
-                    game_options.crosshair[2],
-                    game_options.crosshair[3]
-                ),
-                score_color: color_u8!(
-                    game_options.score[0],
-                    game_options.score[1],
-                    game_options.score[2],
-                    game_options.score[3]
-                ),
-                scale: game_options.scale as f32
+        let mut options = Settings::load_file_options();
+        if options.modes.is_empty() {
+            options.modes = GameMode::builtin(ROUND_DURATION);
+        }
+        // Rounds are still playable without them, `squaretap validate` tells what's wrong
+        options.curves = load_curves().unwrap_or_else(|err| {
+            eprintln!("Warning: {}, playing without difficulty curves", err);
+            return vec![];
+        });
+
+        return options;
+    }
+
+    fn load_file_options() -> GameSettings {
+        match Settings::read_file_options() {
+            Ok(options) => return options,
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    // Options from the config file, the defaults if it doesn't exist
+    fn read_file_options() -> Result<GameSettings, String> {
+        let path = &config_path();
+        if path.exists() {
+            let invalid = |err: &dyn std::fmt::Display| format!("{}: {}", path.display(), err);
+            let options_str = fs::read_to_string(path).map_err(|err| invalid(&err))?;
+            let game_options: GameOptions = toml::from_str(&options_str).map_err(|err| invalid(&err))?;
+            game_options.check().map_err(|err| invalid(&err))?;
+
+            let preset = game_options.theme.theme();
+            let colors = Theme {
+                filled: color_or(&game_options.tile_filled, preset.filled),
+                empty: color_or(&game_options.tile_empty, preset.empty),
+                border: color_or(&game_options.tile_border, preset.border),
+                decoy: color_or(&game_options.tile_decoy, preset.decoy),
+                bonus: color_or(&game_options.tile_bonus, preset.bonus),
+                time: color_or(&game_options.tile_time, preset.time),
+                multi_hit: color_or(&game_options.tile_multi_hit, preset.multi_hit),
+                crosshair: color_or(&game_options.crosshair, preset.crosshair),
+                score: color_or(&game_options.score, preset.score),
+            };
+            // Only colors set in the settings are checked, not the presets'
+            let custom = [
+                &game_options.tile_filled,
+                &game_options.tile_empty,
+                &game_options.tile_border,
+                &game_options.tile_decoy,
+                &game_options.tile_bonus,
+                &game_options.tile_time,
+                &game_options.tile_multi_hit,
+            ];
+            let contrast_warnings = if custom.iter().any(|color| color.is_some()) {
+                colors.contrast_warnings()
+            } else {
+                Vec::new()
             };
+
+            return Ok(GameSettings {
+                language: game_options.language,
+                theme: game_options.theme,
+                colors,
+                tile_cues: game_options.tile_cues,
+                contrast_warnings,
+                crosshair_style: game_options.crosshair_style,
+                crosshair_size: game_options.crosshair_size,
+                crosshair_thickness: game_options.crosshair_thickness,
+                crosshair_gap: game_options.crosshair_gap,
+                crosshair_outline: game_options.crosshair_outline,
+                crosshair_outline_color: color_or(&game_options.crosshair_outline_color, CROSSHAIR_OUTLINE_COLOR),
+                crosshair_image: game_options.crosshair_image,
+                text_styles: game_options
+                    .text_styles
+                    .into_iter()
+                    .map(|style| StyleOverride {
+                        style: style.style,
+                        font: style.font,
+                        size: style.size,
+                        color: style.color.map(|color| color_u8!(color[0], color[1], color[2], color[3])),
+                    })
+                    .collect(),
+                scale: game_options.scale as f32,
+                timer_bar: game_options.timer_bar,
+                effects: game_options.effects,
+                modes: merge_modes(
+                    GameMode::builtin(game_options.round_duration),
+                    game_options.modes,
+                ),
+                curves: Vec::new(),
+                window_mode: game_options.window_mode,
+                resolution: (game_options.resolution[0], game_options.resolution[1]),
+                resizable: game_options.resizable,
+                ui_scale: game_options.ui_scale,
+                master_volume: game_options.master_volume,
+                effects_volume: game_options.effects_volume,
+                muted: game_options.muted,
+                reduced_motion: game_options.reduced_motion,
+                large_text: game_options.large_text,
+                timer_scale: game_options.timer_scale,
+                miss_tolerance: game_options.miss_tolerance,
+                keyboard_layout: game_options.keyboard_layout,
+                hold_to_confirm: game_options.hold_to_confirm,
+            });
         } else {
-            return DEFAULT_GAME_OPTIONS;
+            return Ok(GameSettings {
+                language: default_language(),
+                ..DEFAULT_GAME_OPTIONS
+            });
         }
     }
-    
-    pub fn update(&mut self) {}
-
-    pub fn render(&mut self, font: Font) {
-        let title = "Settings";
-        let title_dimensions = measure_text(title, Some(font), 78, 1.0);
-        draw_text_ex(
-            title,
-            screen_width() / 2.0 - title_dimensions.width / 2.0,
-            screen_height() / 2.0 - 250.0,
-            TextParams {
-                font,
-                font_size: 78,
-                color: WHITE,
-                ..Default::default()
-            },
-        );
 
-        // Key bindings
-        //  Menu
-        draw_text_ex(
-            "Key bindnings:",
-            250.0,
-            screen_height() / 2.0 - 175.0,
-            TextParams {
-                font,
-                font_size: 32,
-                color: WHITE,
-                ..Default::default()
-            },
-        );
+    // `squaretap validate`, checks the config and difficulty curves without opening the game
+    pub fn validate_command() {
+        let options = Settings::read_file_options();
+        let curves = load_curves();
+        let (mut options, curves) = match (options, curves) {
+            (Ok(options), Ok(curves)) => (options, curves),
+            (Err(err), _) | (_, Err(err)) => {
+                eprintln!("Invalid settings: {}", err);
+                exit(1);
+            }
+        };
 
-        draw_text_ex(
-            "Select: Enter",
-            250.0,
-            screen_height() / 2.0 - 125.0,
-            TextParams {
-                font,
-                font_size: 18,
-                color: WHITE,
-                ..Default::default()
-            },
-        );
-        draw_text_ex(
-            "Back: Escape",
-            250.0,
-            screen_height() / 2.0 - 100.0,
-            TextParams {
-                font,
-                font_size: 18,
-                color: WHITE,
-                ..Default::default()
-            },
-        );
-        draw_text_ex(
-            "Move up: ArrowUp",
-            250.0,
-            screen_height() / 2.0 - 75.0,
-            TextParams {
-                font,
-                font_size: 18,
-                color: WHITE,
-                ..Default::default()
-            },
-        );
-        draw_text_ex(
-            "Move down: ArrowDown",
-            250.0,
-            screen_height() / 2.0 - 50.0,
-            TextParams {
-                font,
-                font_size: 18,
-                color: WHITE,
-                ..Default::default()
-            },
-        );
+        if !config_path().exists() {
+            println!("No settings at {}, using the defaults", config_path().display());
+        }
+        if options.modes.is_empty() {
+            options.modes = GameMode::builtin(ROUND_DURATION);
+        }
 
-        draw_text_ex(
-            "Hide info: Tab",
-            250.0,
-            screen_height() / 2.0,
-            TextParams {
-                font,
-                font_size: 18,
-                color: WHITE,
-                ..Default::default()
-            },
-        );
-        draw_text_ex(
-            "Hide grid: G",
-            250.0,
-            screen_height() / 2.0 + 25.0,
-            TextParams {
-                font,
-                font_size: 18,
-                color: WHITE,
-                ..Default::default()
-            },
+        for warning in &options.contrast_warnings {
+            println!("Warning: {}", warning);
+        }
+        for warning in locale::check_languages() {
+            println!("Warning: {}", warning);
+        }
+
+        let mut errors = vec![];
+        if !locale::languages().iter().any(|language| language.code == options.language) {
+            errors.push(format!("language \"{}\" has no file in res/lang", options.language));
+        }
+        for font in options.text_styles.iter().filter_map(|style| style.font.as_ref()) {
+            if !paths().asset(font).exists() {
+                errors.push(format!("text style font \"{}\" doesn't exist in {}", font, paths().assets.display()));
+            }
+        }
+        for mode in &options.modes {
+            if let Some(curve) = &mode.curve {
+                if !curves.iter().any(|c| &c.name == curve) {
+                    errors.push(format!("mode \"{}\" uses unknown curve \"{}\"", mode.name, curve));
+                }
+            }
+        }
+
+        if !errors.is_empty() {
+            for err in &errors {
+                eprintln!("Invalid settings: {}", err);
+            }
+            exit(1);
+        }
+        println!(
+            "Settings are valid: {} modes, {} difficulty curves",
+            options.modes.len(),
+            curves.len()
         );
     }
+    
+    // Changes take effect right away and are written to the config file
+    pub fn update(&mut self) -> Option<MenuEvent<SettingsSelect>> {
+        let event = self.menu.update();
+        let written = match event {
+            Some(MenuEvent::Activated(SettingsSelect::Language)) => {
+                let languages = locale::languages();
+                let i = languages.iter().position(|language| language.code == self.language);
+                let next = i.map_or(0, |i| (i + 1) % languages.len().max(1));
+                if let Some(language) = languages.get(next) {
+                    self.language = language.code.clone();
+                }
+                // The labels, titles included, are rebuilt in the new language
+                locale::set_language(&self.language);
+                self.menu = Settings::menu();
+                write_option("language", &format!("\"{}\"", self.language))
+            }
+            Some(MenuEvent::Activated(SettingsSelect::Theme)) => {
+                let i = ThemePreset::ALL.iter().position(|&theme| theme == self.theme).unwrap_or(0);
+                self.theme = ThemePreset::ALL[(i + 1) % ThemePreset::ALL.len()];
+                write_option("theme", &format!("\"{}\"", self.theme.name()))
+            }
+            Some(MenuEvent::Activated(SettingsSelect::TileCues)) => {
+                self.tile_cues = !self.tile_cues;
+                write_option("tile_cues", &self.tile_cues.to_string())
+            }
+            Some(MenuEvent::Activated(SettingsSelect::TimerBar)) => {
+                self.timer_bar = !self.timer_bar;
+                write_option("timer_bar", &self.timer_bar.to_string())
+            }
+            Some(MenuEvent::Activated(SettingsSelect::Effects)) => {
+                self.effects = !self.effects;
+                write_option("effects", &self.effects.to_string())
+            }
+            Some(MenuEvent::Activated(SettingsSelect::UiScale)) => {
+                self.ui_scale = next_step(&UI_SCALES, self.ui_scale);
+                write_option("ui_scale", &self.ui_scale.to_string())
+            }
+            Some(MenuEvent::Activated(SettingsSelect::MasterVolume)) => {
+                self.master_volume = next_step(&VOLUMES, self.master_volume);
+                write_option("master_volume", &self.master_volume.to_string())
+            }
+            Some(MenuEvent::Activated(SettingsSelect::EffectsVolume)) => {
+                self.effects_volume = next_step(&VOLUMES, self.effects_volume);
+                write_option("effects_volume", &self.effects_volume.to_string())
+            }
+            Some(MenuEvent::Activated(SettingsSelect::Mute)) => {
+                self.muted = !self.muted;
+                write_option("muted", &self.muted.to_string())
+            }
+            Some(MenuEvent::Activated(SettingsSelect::ReducedMotion)) => {
+                self.reduced_motion = !self.reduced_motion;
+                write_option("reduced_motion", &self.reduced_motion.to_string())
+            }
+            Some(MenuEvent::Activated(SettingsSelect::LargeText)) => {
+                self.large_text = !self.large_text;
+                write_option("large_text", &self.large_text.to_string())
+            }
+            Some(MenuEvent::Activated(SettingsSelect::TimerScale)) => {
+                self.timer_scale = next_step(&TIMER_SCALES, self.timer_scale);
+                write_option("timer_scale", &self.timer_scale.to_string())
+            }
+            Some(MenuEvent::Activated(SettingsSelect::MissTolerance)) => {
+                self.miss_tolerance = MISS_TOLERANCES
+                    .iter()
+                    .cloned()
+                    .find(|&misses| misses > self.miss_tolerance)
+                    .unwrap_or(MISS_TOLERANCES[0]);
+                write_option("miss_tolerance", &self.miss_tolerance.to_string())
+            }
+            Some(MenuEvent::Activated(SettingsSelect::KeyboardLayout)) => {
+                let i = KeyboardLayout::ALL.iter().position(|&layout| layout == self.keyboard_layout).unwrap_or(0);
+                self.keyboard_layout = KeyboardLayout::ALL[(i + 1) % KeyboardLayout::ALL.len()];
+                write_option("keyboard_layout", &format!("\"{}\"", self.keyboard_layout.name()))
+            }
+            Some(MenuEvent::Activated(SettingsSelect::HoldToConfirm)) => {
+                self.hold_to_confirm = !self.hold_to_confirm;
+                write_option("hold_to_confirm", &self.hold_to_confirm.to_string())
+            }
+            _ => return event,
+        };
+
+        self.update_labels();
+        let path = ("path", config_path().display().to_string());
+        self.status = match written {
+            Ok(true) => tr_with("settings.saved", &[path]),
+            Ok(false) => tr_with("settings.not_saved", &[path]),
+            Err(err) => tr_with("settings.save_failed", &[("error", err)]),
+        };
+        if let Some(MenuEvent::Activated(SettingsSelect::Theme)) = event {
+            if let Some(warning) = self.themed_options().contrast_warnings.first() {
+                self.status = warning.clone();
+            }
+        }
+        return event;
+    }
+
+    pub fn render(&mut self) {
+        self.menu.render();
+
+        let status = at(Anchor::BottomLeft, 250.0, -32.0);
+        draw_text_ex(&self.status, status.x, status.y, text::params(TextStyle::Small));
+    }
 }
 
 impl Default for Settings {
     fn default() -> Settings {
-        return Settings {};
+        let mut settings = Settings {
+            theme: ThemePreset::Default,
+            tile_cues: false,
+            timer_bar: true,
+            effects: true,
+            ui_scale: 1.0,
+            master_volume: 1.0,
+            effects_volume: 1.0,
+            muted: false,
+            reduced_motion: false,
+            large_text: false,
+            timer_scale: 1.0,
+            miss_tolerance: 0,
+            keyboard_layout: KeyboardLayout::Default,
+            hold_to_confirm: false,
+            language: FALLBACK_LANGUAGE.to_owned(),
+            status: String::new(),
+            menu: Settings::menu(),
+        };
+        settings.update_labels();
+
+        return settings;
+    }
+}
+
+// The step after `value`, back to the first after the last
+fn next_step(steps: &[f32], value: f32) -> f32 {
+    return steps
+        .iter()
+        .cloned()
+        .find(|&step| step > value + 0.01)
+        .unwrap_or(steps[0]);
+}
+
+// Sets a top level `key = value` in the config file and keeps the rest of it, comments included.
+// Returns false if there is no config file to write to.
+fn write_option(key: &str, value: &str) -> Result<bool, String> {
+    let path = &config_path();
+    if !path.exists() {
+        return Ok(false);
     }
+
+    let text = fs::read_to_string(path).map_err(|err| err.to_string())?;
+    let mut lines: Vec<String> = text.lines().map(str::to_owned).collect();
+    let tables = lines
+        .iter()
+        .position(|line| line.trim_start().starts_with('['))
+        .unwrap_or(lines.len());
+    let line = format!("{} = {}", key, value);
+    let existing = lines[..tables].iter().position(|line| {
+        return !line.trim_start().starts_with('#')
+            && line.split('=').next().map(str::trim) == Some(key);
+    });
+    match existing {
+        Some(i) => lines[i] = line,
+        None => lines.insert(tables, line),
+    }
+
+    fs::write(path, lines.join("\n") + "\n").map_err(|err| err.to_string())?;
+    return Ok(true);
+}
+
+// Modes from the settings file replace built in modes with the same name, others are appended
+fn merge_modes(mut modes: Vec<GameMode>, custom: Vec<GameMode>) -> Vec<GameMode> {
+    for mode in custom {
+        match modes.iter_mut().find(|m| m.name == mode.name) {
+            Some(existing) => *existing = mode,
+            None => modes.push(mode),
+        }
+    }
+
+    return modes;
 }
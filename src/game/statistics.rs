@@ -0,0 +1,271 @@
+use macroquad::prelude::*;
+
+use super::heatmap::Heatmap;
+use super::history::History;
+use super::layout::{at, draw_text_centred, px, Anchor};
+use super::locale::{tr, tr_with};
+use super::menu::{is_back_pressed, Button, Switcher};
+use super::text::{self, TextStyle};
+
+// Rounds shown in the score over time chart
+const SCORE_ROUNDS: usize = 50;
+// Reaction time histogram, the last bucket holds everything slower
+const REACTION_BUCKETS: usize = 20;
+const REACTION_BUCKET_SIZE: f32 = 0.05;
+
+const CHART_COLOR: Color = color_u8!(40, 130, 115, 255);
+const PANEL_COLOR: Color = color_u8!(0, 0, 0, 160);
+
+pub enum StatisticsAction {
+    Export,
+    Import,
+    Back,
+}
+
+pub struct Statistics {
+    pub status: String,
+    modes: Vec<String>,
+    selected: usize,
+    rounds: usize,
+    scores: Vec<f32>,
+    reactions: Vec<f32>,
+    taps_per_second: Vec<f32>,
+    heatmap: Heatmap,
+    bests: Vec<(String, u32, usize)>,
+    switcher: Switcher,
+    back: Button,
+    export: Button,
+    import: Button,
+}
+
+impl Statistics {
+    pub fn new() -> Self {
+        return Self {
+            ..Default::default()
+        };
+    }
+
+    pub fn setup(&mut self, history: &History) {
+        self.status = String::new();
+        self.modes = vec![];
+        self.bests = vec![];
+        for round in &history.rounds {
+            let round_mode = round.highscore_mode();
+            match self.bests.iter_mut().find(|(mode, _, _)| *mode == round_mode) {
+                Some(best) => {
+                    best.1 = best.1.max(round.score);
+                    best.2 += 1;
+                }
+                None => {
+                    self.modes.push(round_mode.clone());
+                    self.bests.push((round_mode, round.score, 1));
+                }
+            }
+        }
+
+        if self.selected >= self.modes.len() {
+            self.selected = 0;
+        }
+        self.compute(history);
+    }
+
+    // Collects the chart data for the selected mode
+    fn compute(&mut self, history: &History) {
+        self.scores = vec![];
+        self.reactions = vec![0.0; REACTION_BUCKETS];
+        self.taps_per_second = vec![];
+        self.heatmap = Heatmap::new();
+
+        let mode = match self.modes.get(self.selected) {
+            Some(mode) => mode,
+            None => return,
+        };
+        let rounds: Vec<_> = history.rounds_for(mode).collect();
+        self.rounds = rounds.len();
+
+        let first = rounds.len().saturating_sub(SCORE_ROUNDS);
+        self.scores = rounds[first..].iter().map(|round| round.score as f32).collect();
+
+        for tap in rounds.iter().flat_map(|round| round.taps.iter()) {
+            self.heatmap.add(tap);
+            if tap.hit {
+                let bucket = (tap.reaction / REACTION_BUCKET_SIZE) as usize;
+                self.reactions[bucket.min(REACTION_BUCKETS - 1)] += 1.0;
+            }
+        }
+
+        if let Some(last) = rounds.last() {
+            self.taps_per_second = vec![0.0; last.length.ceil().max(1.0) as usize];
+            for tap in last.taps.iter().filter(|tap| tap.hit) {
+                let second = (tap.time as usize).min(self.taps_per_second.len() - 1);
+                self.taps_per_second[second] += 1.0;
+            }
+        }
+    }
+
+    pub fn update(&mut self, history: &History) -> Option<StatisticsAction> {
+        if is_back_pressed() || self.back.clicked() {
+            return Some(StatisticsAction::Back);
+        } else if is_key_pressed(KeyCode::E) || self.export.clicked() {
+            return Some(StatisticsAction::Export);
+        } else if is_key_pressed(KeyCode::I) || self.import.clicked() {
+            return Some(StatisticsAction::Import);
+        }
+
+        let count = self.modes.len();
+        if count > 0 {
+            match self.switcher.update() {
+                -1 => self.selected = (self.selected + count - 1) % count,
+                1 => self.selected = (self.selected + 1) % count,
+                _ => return None,
+            }
+            self.compute(history);
+        }
+
+        return None;
+    }
+
+    pub fn render(&mut self) {
+        self.back.render_back();
+        self.export.render(at(Anchor::BottomLeft, 250.0, -18.0), TextStyle::Small);
+        self.import.render(at(Anchor::BottomLeft, 450.0, -18.0), TextStyle::Small);
+        let footer = at(Anchor::BottomLeft, 650.0, -18.0);
+        draw_text_ex(&self.status, footer.x, footer.y, text::params(TextStyle::Small));
+
+        let title = &tr("statistics.title");
+        let title_position = at(Anchor::Centre, 0.0, -250.0);
+        draw_text_centred(title, title_position.x, title_position.y, text::params(TextStyle::Title));
+
+        let mode = match self.modes.get(self.selected) {
+            Some(mode) => mode,
+            None => {
+                let position = at(Anchor::Left, 250.0, -150.0);
+                draw_text_ex(&tr("statistics.empty"), position.x, position.y, text::params(TextStyle::MenuItem));
+                return;
+            }
+        };
+
+        let mode_text = tr_with("statistics.rounds", &[("mode", mode.clone()), ("rounds", self.rounds.to_string())]);
+        self.switcher.render(&mode_text, -200.0, TextStyle::MenuItem);
+
+        // Panels in a 3x2 grid below the title, the heatmap takes up the whole right column,
+        // with room for the footer underneath
+        let gap = px(20.0);
+        let top_left = at(Anchor::Left, 100.0, -170.0);
+        let bottom = at(Anchor::Bottom, 0.0, -50.0).y;
+        let (left, top) = (top_left.x, top_left.y);
+        let width = (screen_width() - left * 2.0 - gap * 2.0) / 3.0;
+        let height = (bottom - top - gap) / 2.0;
+        let panels = [
+            Rect::new(left, top, width, height),
+            Rect::new(left + width + gap, top, width, height),
+            Rect::new(left, top + height + gap, width, height),
+            Rect::new(left + width + gap, top + height + gap, width, height),
+            Rect::new(left + (width + gap) * 2.0, top, width, height * 2.0 + gap),
+        ];
+
+        let chart = draw_panel(panels[0], &tr("statistics.score_over_time"));
+        draw_line_chart(&self.scores, chart);
+        draw_range_labels(&self.scores, chart);
+
+        let chart = draw_panel(panels[1], &tr("statistics.reaction_times"));
+        draw_bar_chart(&self.reactions, chart);
+
+        let chart = draw_panel(panels[2], &tr("statistics.hits_per_second"));
+        draw_bar_chart(&self.taps_per_second, chart);
+        draw_range_labels(&self.taps_per_second, chart);
+
+        let chart = draw_panel(panels[3], &tr("statistics.personal_bests"));
+        for (i, (mode, best, rounds)) in self.bests.iter().enumerate() {
+            draw_text_ex(
+                &tr_with(
+                    "statistics.best",
+                    &[("mode", mode.clone()), ("best", best.to_string()), ("rounds", rounds.to_string())],
+                ),
+                chart.x,
+                chart.y + px(20.0 + i as f32 * 24.0),
+                text::params(TextStyle::Small),
+            );
+        }
+
+        let chart = draw_panel(panels[4], &tr("statistics.heatmap"));
+        let size = chart.w.min(chart.h);
+        self.heatmap.render(chart.x - px(20.0), chart.y, size);
+    }
+}
+
+impl Default for Statistics {
+    fn default() -> Statistics {
+        return Statistics {
+            status: String::new(),
+            modes: vec![],
+            selected: 0,
+            rounds: 0,
+            scores: vec![],
+            reactions: vec![],
+            taps_per_second: vec![],
+            heatmap: Heatmap::new(),
+            bests: vec![],
+            switcher: Switcher::new(),
+            back: Button::new(&tr("button.back")),
+            export: Button::new(&tr("button.export")),
+            import: Button::new(&tr("button.import")),
+        };
+    }
+}
+
+// Draws the panel background and title, returns the area left for the chart
+fn draw_panel(rect: Rect, title: &str) -> Rect {
+    draw_rectangle(rect.x, rect.y, rect.w, rect.h, PANEL_COLOR);
+    draw_rectangle_lines(rect.x, rect.y, rect.w, rect.h, px(2.0), WHITE);
+    draw_text_ex(title, rect.x + px(10.0), rect.y + px(26.0), text::params(TextStyle::Small));
+
+    let (margin, padding) = (px(40.0), px(60.0));
+    return Rect::new(rect.x + margin, rect.y + margin, rect.w - padding, rect.h - padding);
+}
+
+fn max_value(values: &[f32]) -> f32 {
+    return values.iter().cloned().fold(0.0, f32::max).max(1.0);
+}
+
+fn draw_line_chart(values: &[f32], rect: Rect) {
+    let max = max_value(values);
+    let step = rect.w / (values.len().max(2) - 1) as f32;
+    let point = |i: usize| {
+        return vec2(rect.x + i as f32 * step, rect.y + rect.h - values[i] / max * rect.h);
+    };
+
+    for i in 1..values.len() {
+        let (start, end) = (point(i - 1), point(i));
+        draw_line(start.x, start.y, end.x, end.y, px(2.0), CHART_COLOR);
+    }
+    for i in 0..values.len() {
+        let p = point(i);
+        draw_circle(p.x, p.y, px(3.0), WHITE);
+    }
+}
+
+fn draw_bar_chart(values: &[f32], rect: Rect) {
+    let max = max_value(values);
+    let width = rect.w / values.len().max(1) as f32;
+
+    for (i, value) in values.iter().enumerate() {
+        let height = value / max * rect.h;
+        draw_rectangle(
+            rect.x + i as f32 * width + 1.0,
+            rect.y + rect.h - height,
+            (width - 2.0).max(1.0),
+            height,
+            CHART_COLOR,
+        );
+    }
+}
+
+// Top and bottom of the value axis
+fn draw_range_labels(values: &[f32], rect: Rect) {
+    let params = text::params(TextStyle::Label);
+
+    let x = rect.x - px(35.0);
+    draw_text_ex(&format!("{}", max_value(values)), x, rect.y + px(10.0), params);
+    draw_text_ex("0", x, rect.y + rect.h, params);
+}
@@ -0,0 +1,150 @@
+use std::{
+    fs,
+    sync::{OnceLock, RwLock},
+};
+
+use macroquad::prelude::*;
+
+use serde_derive::Deserialize;
+
+use super::layout::font_size;
+use super::paths::paths;
+
+// Every piece of text is drawn in one of these, so fonts, sizes and colors are set in one place
+#[derive(Deserialize, Clone, Copy, PartialEq)]
+pub enum TextStyle {
+    // Screen titles
+    Title,
+    // The score on the results screen
+    Heading,
+    // Menu items, buttons and tables
+    MenuItem,
+    // The info and labels over the board during a round
+    Hud,
+    // Lines of results
+    Body,
+    // Descriptions, panel titles and status lines
+    Small,
+    // Numbers on charts and the heatmap
+    Label,
+}
+
+impl TextStyle {
+    pub const ALL: [TextStyle; 7] = [
+        TextStyle::Title,
+        TextStyle::Heading,
+        TextStyle::MenuItem,
+        TextStyle::Hud,
+        TextStyle::Body,
+        TextStyle::Small,
+        TextStyle::Label,
+    ];
+
+    // Size in reference pixels
+    const fn size(self) -> u16 {
+        match self {
+            TextStyle::Title => return 78,
+            TextStyle::Heading => return 48,
+            TextStyle::MenuItem => return 32,
+            TextStyle::Hud => return 32,
+            TextStyle::Body => return 24,
+            TextStyle::Small => return 18,
+            TextStyle::Label => return 14,
+        }
+    }
+}
+
+// A style as set in the settings, anything left out keeps the default
+#[derive(Clone)]
+pub struct StyleOverride {
+    pub style: TextStyle,
+    // From the resources, the language's font if there is none
+    pub font: Option<String>,
+    pub size: Option<u16>,
+    pub color: Option<Color>,
+}
+
+#[derive(Clone, Copy)]
+struct Style {
+    // None for the language's font
+    font: Option<Font>,
+    size: u16,
+    color: Color,
+}
+
+struct Styles {
+    font: Font,
+    styles: [Style; 7],
+}
+
+static STYLES: OnceLock<RwLock<Styles>> = OnceLock::new();
+
+fn styles() -> &'static RwLock<Styles> {
+    return STYLES.get_or_init(|| {
+        RwLock::new(Styles {
+            font: Font::default(),
+            styles: TextStyle::ALL.map(|style| Style {
+                font: None,
+                size: style.size(),
+                color: WHITE,
+            }),
+        })
+    });
+}
+
+fn index(style: TextStyle) -> usize {
+    return TextStyle::ALL.iter().position(|&s| s == style).unwrap();
+}
+
+// The font of the language, used by every style without a font of its own
+pub fn set_font(font: Font) {
+    styles().write().unwrap().font = font;
+}
+
+// Back to the defaults with the overrides on top. Fonts that can't be loaded are left out.
+pub fn set_styles(overrides: &[StyleOverride]) {
+    let mut styles = styles().write().unwrap();
+    for style in TextStyle::ALL {
+        styles.styles[index(style)] = Style {
+            font: None,
+            size: style.size(),
+            color: WHITE,
+        };
+    }
+
+    for style_override in overrides {
+        let style = &mut styles.styles[index(style_override.style)];
+        if let Some(path) = &style_override.font {
+            let font = fs::read(paths().asset(path))
+                .map_err(|err| err.to_string())
+                .and_then(|bytes| load_ttf_font_from_bytes(&bytes).map_err(|err| format!("{:?}", err)));
+            match font {
+                Ok(font) => style.font = Some(font),
+                Err(err) => eprintln!("Couldn't load the font {}, using the default: {}", path, err),
+            }
+        }
+        if let Some(size) = style_override.size {
+            style.size = size;
+        }
+        if let Some(color) = style_override.color {
+            style.color = color;
+        }
+    }
+}
+
+// Parameters to draw text in the style with, scaled to the screen
+pub fn params(style: TextStyle) -> TextParams {
+    let styles = styles().read().unwrap();
+    let resolved = styles.styles[index(style)];
+    return TextParams {
+        font: resolved.font.unwrap_or(styles.font),
+        font_size: font_size(resolved.size),
+        color: resolved.color,
+        ..Default::default()
+    };
+}
+
+pub fn measure(text: &str, style: TextStyle) -> TextDimensions {
+    let params = params(style);
+    return measure_text(text, Some(params.font), params.font_size, params.font_scale);
+}
@@ -0,0 +1,182 @@
+use macroquad::prelude::*;
+
+use serde_derive::Deserialize;
+
+// WCAG's minimum contrast for things that have to be told apart, like lit and empty tiles
+pub const MIN_CONTRAST: f32 = 3.0;
+
+// Built in color sets, the colors in the settings override them one by one
+#[derive(Deserialize, Clone, Copy, PartialEq)]
+pub enum ThemePreset {
+    Default,
+    // For deuteranopia and protanopia, no red against green
+    RedGreen,
+    // For tritanopia, no blue against yellow or green
+    BlueYellow,
+    HighContrast,
+}
+
+impl ThemePreset {
+    pub const ALL: [ThemePreset; 4] = [
+        ThemePreset::Default,
+        ThemePreset::RedGreen,
+        ThemePreset::BlueYellow,
+        ThemePreset::HighContrast,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ThemePreset::Default => return "Default",
+            ThemePreset::RedGreen => return "RedGreen",
+            ThemePreset::BlueYellow => return "BlueYellow",
+            ThemePreset::HighContrast => return "HighContrast",
+        }
+    }
+
+    pub const fn theme(self) -> Theme {
+        match self {
+            ThemePreset::Default => {
+                return Theme {
+                    filled: color_u8!(45, 55, 65, 255),
+                    empty: color_u8!(40, 130, 115, 255),
+                    border: color_u8!(255, 255, 255, 255),
+                    decoy: color_u8!(180, 40, 40, 255),
+                    bonus: color_u8!(220, 180, 40, 255),
+                    time: color_u8!(60, 120, 220, 255),
+                    multi_hit: color_u8!(140, 70, 170, 255),
+                    crosshair: color_u8!(255, 0, 0, 255),
+                    score: color_u8!(0, 255, 0, 255),
+                }
+            }
+            // From the Okabe-Ito palette
+            ThemePreset::RedGreen => {
+                return Theme {
+                    filled: color_u8!(0, 114, 178, 255),
+                    empty: color_u8!(25, 25, 25, 255),
+                    border: color_u8!(255, 255, 255, 255),
+                    decoy: color_u8!(213, 94, 0, 255),
+                    bonus: color_u8!(240, 228, 66, 255),
+                    time: color_u8!(86, 180, 233, 255),
+                    multi_hit: color_u8!(204, 121, 167, 255),
+                    crosshair: color_u8!(255, 255, 255, 255),
+                    score: color_u8!(86, 180, 233, 255),
+                }
+            }
+            ThemePreset::BlueYellow => {
+                return Theme {
+                    filled: color_u8!(240, 240, 240, 255),
+                    empty: color_u8!(25, 25, 25, 255),
+                    border: color_u8!(128, 128, 128, 255),
+                    decoy: color_u8!(213, 94, 0, 255),
+                    bonus: color_u8!(204, 121, 167, 255),
+                    time: color_u8!(0, 158, 115, 255),
+                    multi_hit: color_u8!(230, 159, 0, 255),
+                    crosshair: color_u8!(230, 159, 0, 255),
+                    score: color_u8!(240, 240, 240, 255),
+                }
+            }
+            ThemePreset::HighContrast => {
+                return Theme {
+                    filled: color_u8!(255, 255, 255, 255),
+                    empty: color_u8!(0, 0, 0, 255),
+                    border: color_u8!(255, 255, 0, 255),
+                    decoy: color_u8!(255, 60, 60, 255),
+                    bonus: color_u8!(255, 150, 0, 255),
+                    time: color_u8!(0, 200, 255, 255),
+                    multi_hit: color_u8!(200, 100, 255, 255),
+                    crosshair: color_u8!(0, 255, 0, 255),
+                    score: color_u8!(255, 255, 0, 255),
+                }
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct Theme {
+    pub filled: Color,
+    pub empty: Color,
+    pub border: Color,
+    pub decoy: Color,
+    pub bonus: Color,
+    pub time: Color,
+    pub multi_hit: Color,
+    pub crosshair: Color,
+    pub score: Color,
+}
+
+impl Theme {
+    // Lit tiles that are too close to the empty tile color to tell apart without the color, and tiles
+    // the grid, outlines and score popups drawn over them in the border color would disappear on
+    pub fn contrast_warnings(&self) -> Vec<String> {
+        let lit = [
+            ("tile_filled", self.filled),
+            ("tile_decoy", self.decoy),
+            ("tile_bonus", self.bonus),
+            ("tile_time", self.time),
+            ("tile_multi_hit", self.multi_hit),
+        ];
+        let mut pairs: Vec<(&str, Color, &str, Color)> = vec![];
+        for &(name, color) in &lit {
+            pairs.push((name, color, "tile_empty", self.empty));
+        }
+        for &(name, color) in lit.iter().chain([("tile_empty", self.empty)].iter()) {
+            pairs.push((name, color, "tile_border", self.border));
+        }
+
+        return pairs
+            .into_iter()
+            .map(|(name, color, other, other_color)| (name, other, contrast(color, other_color)))
+            .filter(|&(_, _, ratio)| ratio < MIN_CONTRAST)
+            .map(|(name, other, ratio)| {
+                format!(
+                    "{} and {} are hard to tell apart, contrast {:.1}:1 is below {}:1",
+                    name, other, ratio, MIN_CONTRAST
+                )
+            })
+            .collect();
+    }
+}
+
+// WCAG contrast ratio between two colors, from 1 for the same brightness to 21 for black on white
+pub fn contrast(a: Color, b: Color) -> f32 {
+    let (a, b) = (luminance(a), luminance(b));
+    return (a.max(b) + 0.05) / (a.min(b) + 0.05);
+}
+
+// Relative luminance of a color, alpha is ignored
+fn luminance(color: Color) -> f32 {
+    let linear = |channel: f32| {
+        if channel <= 0.03928 {
+            channel / 12.92
+        } else {
+            ((channel + 0.055) / 1.055).powf(2.4)
+        }
+    };
+
+    return 0.2126 * linear(color.r) + 0.7152 * linear(color.g) + 0.0722 * linear(color.b);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn presets_keep_tiles_apart_from_the_border() {
+        for preset in ThemePreset::ALL {
+            let theme = preset.theme();
+            for color in [theme.filled, theme.empty, theme.decoy, theme.bonus, theme.time, theme.multi_hit] {
+                assert!(color != theme.border, "{} has a tile in its border color", preset.name());
+            }
+        }
+    }
+
+    #[test]
+    fn warns_about_tiles_like_the_border() {
+        let mut theme = ThemePreset::RedGreen.theme();
+        theme.multi_hit = theme.border;
+        let warnings = theme.contrast_warnings();
+        assert!(warnings.iter().any(|warning| warning.starts_with("tile_multi_hit and tile_border")));
+        assert!(!warnings.iter().any(|warning| warning.starts_with("tile_multi_hit and tile_empty")));
+    }
+}
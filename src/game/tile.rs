@@ -0,0 +1,119 @@
+use ::rand::{rngs::StdRng, Rng};
+use serde_derive::Deserialize;
+
+// Points of a bonus tile compared to a normal one
+pub const BONUS_MULTIPLIER: u32 = 3;
+// Seconds added by a time tile
+pub const TIME_EXTENSION: f32 = 2.0;
+// Hits needed to clear a multi-hit tile
+pub const MULTI_HITS: u8 = 3;
+// Decoys always expire, without any penalty
+pub const DECOY_LIFETIME: f32 = 2.5;
+pub const MAX_DECOYS: usize = 3;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum TileKind {
+    Empty,
+    Normal,
+    // Must not be clicked, ends the round like a miss
+    Decoy,
+    Bonus,
+    Time,
+    MultiHit,
+}
+
+impl TileKind {
+    // Tiles that should be clicked, these count towards the lit tiles of the difficulty
+    pub fn is_target(&self) -> bool {
+        return !matches!(self, TileKind::Empty | TileKind::Decoy);
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct Tile {
+    pub kind: TileKind,
+    // Seconds left before the tile expires
+    pub life: f32,
+    // Seconds the tile was lit for, 0 if it never expires
+    pub lifetime: f32,
+    pub hits_left: u8,
+}
+
+impl Tile {
+    pub fn new(kind: TileKind, lifetime: f32) -> Self {
+        let hits_left = if kind == TileKind::MultiHit {
+            MULTI_HITS
+        } else {
+            1
+        };
+
+        return Self {
+            kind,
+            life: lifetime,
+            lifetime,
+            hits_left,
+        };
+    }
+
+    pub fn empty() -> Self {
+        return Tile::new(TileKind::Empty, 0.0);
+    }
+}
+
+// Relative chance of each kind being picked when a tile is lit
+#[derive(Deserialize, Clone, Copy)]
+pub struct TileWeights {
+    #[serde(default = "default_normal")]
+    pub normal: f32,
+    #[serde(default)]
+    pub decoy: f32,
+    #[serde(default)]
+    pub bonus: f32,
+    #[serde(default)]
+    pub time: f32,
+    #[serde(default)]
+    pub multi_hit: f32,
+}
+
+fn default_normal() -> f32 {
+    return 1.0;
+}
+
+impl TileWeights {
+    pub fn pick(&self, rng: &mut StdRng) -> TileKind {
+        let weights = [
+            (TileKind::Normal, self.normal),
+            (TileKind::Decoy, self.decoy),
+            (TileKind::Bonus, self.bonus),
+            (TileKind::Time, self.time),
+            (TileKind::MultiHit, self.multi_hit),
+        ];
+
+        let total: f32 = weights.iter().map(|(_, weight)| weight.max(0.0)).sum();
+        if total <= 0.0 {
+            return TileKind::Normal;
+        }
+
+        let mut roll = rng.gen::<f32>() * total;
+        for (kind, weight) in weights.iter() {
+            roll -= weight.max(0.0);
+            if roll < 0.0 {
+                return *kind;
+            }
+        }
+
+        return TileKind::Normal;
+    }
+}
+
+impl Default for TileWeights {
+    fn default() -> TileWeights {
+        return TileWeights {
+            normal: default_normal(),
+            decoy: 0.0,
+            bonus: 0.0,
+            time: 0.0,
+            multi_hit: 0.0,
+        };
+    }
+}
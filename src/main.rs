@@ -1,36 +1,91 @@
+#![allow(clippy::needless_return)]
+// Savefile's derive generates its impls inside a const block and checks versions by hand
+#![allow(non_local_definitions, clippy::manual_range_contains)]
+
 #[macro_use]
 extern crate savefile_derive;
 
-use macroquad::prelude::*;
+use std::process::exit;
+
+use macroquad::{prelude::*, Window};
 
+mod cli;
 mod game;
-use game::Game;
+use cli::{Command, Options, USAGE};
+use game::settings::{GameSettings, Settings, WindowMode};
+use game::{export, highscore::Highscore, layout, paths::set_paths, profiles, Game};
 
-fn window_config() -> Conf {
+// Window from the settings, --windowed and --resolution take precedence
+fn window_config(options: &Options, settings: &GameSettings) -> Conf {
+    let (window_width, window_height) = options.resolution.unwrap_or(settings.resolution);
     return Conf {
         window_title: "SquareTap".to_owned(),
-        fullscreen: true,
+        window_width,
+        window_height,
+        fullscreen: !options.windowed && settings.window_mode != WindowMode::Windowed,
         high_dpi: true,
-        window_resizable: false,
+        window_resizable: settings.resizable,
         ..Default::default()
     };
 }
 
-#[macroquad::main(window_config)]
-async fn main() {
+// The camera maps one unit to one pixel, so it's rebuilt whenever the window changes size
+fn update_camera(size: (f32, f32)) {
+    let camera = Camera2D::from_display_rect(Rect::new(0.0, 0.0, size.0, size.1));
+    set_camera(&camera);
+    layout::update_scale();
+}
+
+fn main() {
+    let mut options = match Options::parse(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(err) => {
+            eprintln!("{}\n\n{}", err, USAGE);
+            exit(2);
+        }
+    };
+    set_paths(std::mem::take(&mut options.paths));
+    profiles::select_saved();
+
+    // Commands that don't open the game
+    match &options.command {
+        Command::Play => {}
+        Command::Highscores => return Highscore::print_command(),
+        Command::Validate => return Settings::validate_command(),
+        Command::Export(dir) => return export::export_command(dir.as_deref()),
+        Command::Import(path) => return export::import_command(path.as_deref()),
+        Command::Help => return println!("{}", USAGE),
+    }
+
+    let settings = Settings::load_options();
+    if let Some(mode) = &options.mode {
+        if !settings.modes.iter().any(|m| &m.name == mode) {
+            eprintln!("Unknown mode \"{}\"", mode);
+            exit(2);
+        }
+    }
+
+    Window::from_config(window_config(&options, &settings), run(options));
+}
+
+async fn run(options: Options) {
+    // Before setting up, which already lays things out
+    let mut screen_size = (screen_width(), screen_height());
+    update_camera(screen_size);
+
     let mut game = Game::new();
     game.setup().await;
+    if let Some(mode) = &options.mode {
+        game.start_round(mode, options.seed);
+    }
 
-    let camera = Camera2D::from_display_rect(Rect::new(
-        0.0,
-        0.0,
-        screen_width() as f32,
-        screen_height() as f32,
-    ));
-    set_camera(&camera);
+    while !game.closing() {
+        if screen_size != (screen_width(), screen_height()) {
+            screen_size = (screen_width(), screen_height());
+            update_camera(screen_size);
+        }
 
-    while game.state != game::GameState::Closing {
-        game.update();
+        game.update().await;
         game.render();
 
         next_frame().await;